@@ -0,0 +1,259 @@
+use std::{collections::HashSet, sync::Arc};
+
+use actix_web::{delete, get, post, put, web, HttpResponse};
+use bson::{oid::ObjectId, DateTime};
+use chrono::Utc;
+use log::info;
+use mongodb::Database;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    api_endpoints::revoke_player_tokens,
+    api_error::{ApiError, ApiResult},
+    auth::{api_key::ApiKey, role_authorization::KNOWN_ROLES},
+    game::player::Player,
+    game_endpoints::to_rfc3339,
+    AppState,
+};
+
+#[derive(Deserialize)]
+struct PlayerRolesRequest {
+    roles: HashSet<String>,
+}
+
+#[derive(Deserialize)]
+struct MergePlayerRequest {
+    /// Player merged into the path player. Source player is deleted
+    source_player_id: String,
+}
+
+#[derive(Deserialize)]
+struct ApiKeyRequest {
+    /// Key owner, e.g. `data pipeline`
+    name: String,
+    #[serde(default)]
+    scopes: Vec<String>,
+    /// Key never expires when not set
+    expires_in_days: Option<u32>,
+}
+
+/// Api key representation returned to admins. Key hash is never returned
+#[derive(Serialize)]
+struct ApiKeyView {
+    api_key_id: String,
+    name: String,
+    scopes: Vec<String>,
+    date_created: String,
+    expires_at: Option<String>,
+    date_revoked: Option<String>,
+}
+
+impl From<ApiKey> for ApiKeyView {
+    fn from(api_key: ApiKey) -> Self {
+        Self {
+            api_key_id: api_key.id.to_hex(),
+            name: api_key.name,
+            scopes: api_key.scopes,
+            date_created: to_rfc3339(api_key.date_created),
+            expires_at: api_key.expires_at.map(to_rfc3339),
+            date_revoked: api_key.date_revoked.map(to_rfc3339),
+        }
+    }
+}
+
+/// Created api key. Plaintext key is only returned here
+#[derive(Serialize)]
+struct CreatedApiKeyView {
+    #[serde(flatten)]
+    details: ApiKeyView,
+    api_key: String,
+}
+
+fn validate_roles<'a>(roles: impl IntoIterator<Item = &'a String>) -> ApiResult<()> {
+    match roles
+        .into_iter()
+        .find(|role| !KNOWN_ROLES.contains(&role.as_str()))
+    {
+        Some(unknown_role) => Err(ApiError::Validation(format!(
+            "{unknown_role} is not a known role!"
+        ))),
+        None => Ok(()),
+    }
+}
+
+fn parse_player_id(player_id: &str) -> ApiResult<ObjectId> {
+    ObjectId::parse_str(player_id).map_err(|_| ApiError::Validation("invalid player id!".into()))
+}
+
+/// Replace player roles. New roles are included in player api tokens after the next token refresh
+#[put("/players/{player_id}/roles")]
+async fn set_player_roles(
+    db: web::Data<Arc<Database>>,
+    player_id: web::Path<String>,
+    req_body: web::Json<PlayerRolesRequest>,
+) -> ApiResult<HttpResponse> {
+    let player_id = parse_player_id(&player_id)?;
+
+    validate_roles(&req_body.roles)?;
+
+    info!("Setting player {player_id} roles...");
+    Player::set_roles(&db, player_id, &req_body.roles).await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Merge two players, e.g. when the player can no longer sign in with one of the identities
+#[post("/players/{player_id}/merge")]
+async fn merge_players(
+    data: web::Data<Arc<AppState>>,
+    db: web::Data<Arc<Database>>,
+    player_id: web::Path<String>,
+    req_body: web::Json<MergePlayerRequest>,
+) -> ApiResult<HttpResponse> {
+    let target_player_id = parse_player_id(&player_id)?;
+    let source_player_id = parse_player_id(&req_body.source_player_id)?;
+
+    info!("Merging player {source_player_id} into {target_player_id}...");
+    Player::merge_players(&db, target_player_id, source_player_id).await?;
+    revoke_player_tokens(&data, &db, &[source_player_id]).await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Api key expiration date. Key must be valid for at least a day, and expire within the supported date range
+fn get_api_key_expiration(expires_in_days: u32) -> ApiResult<DateTime> {
+    let invalid_expiration = || {
+        ApiError::Validation(
+            "api key expiration must be at least 1 day and within the supported date range!".into(),
+        )
+    };
+
+    if expires_in_days == 0 {
+        return Err(invalid_expiration());
+    }
+
+    let expires_at = chrono::Duration::try_days(expires_in_days as i64)
+        .and_then(|expires_in| Utc::now().checked_add_signed(expires_in))
+        .ok_or_else(invalid_expiration)?;
+
+    Ok(DateTime::from_millis(expires_at.timestamp_millis()))
+}
+
+/// Create api key for a service or a script. Scopes are the same as player roles
+#[post("/api-keys")]
+async fn create_api_key(
+    db: web::Data<Arc<Database>>,
+    req_body: web::Json<ApiKeyRequest>,
+) -> ApiResult<HttpResponse> {
+    let req_body = req_body.into_inner();
+
+    if req_body.name.trim().is_empty() {
+        return Err(ApiError::Validation("api key name is required!".into()));
+    }
+
+    validate_roles(&req_body.scopes)?;
+
+    let expires_at = req_body
+        .expires_in_days
+        .map(get_api_key_expiration)
+        .transpose()?;
+
+    info!("Creating api key for {}...", req_body.name);
+    let (api_key, plaintext_key) =
+        ApiKey::create(&db, &req_body.name, req_body.scopes, expires_at).await?;
+
+    Ok(HttpResponse::Created().json(CreatedApiKeyView {
+        details: ApiKeyView::from(api_key),
+        api_key: plaintext_key,
+    }))
+}
+
+#[get("/api-keys")]
+async fn get_api_keys(db: web::Data<Arc<Database>>) -> ApiResult<HttpResponse> {
+    let api_keys: Vec<ApiKeyView> = ApiKey::get_all(&db)
+        .await?
+        .into_iter()
+        .map(ApiKeyView::from)
+        .collect();
+
+    Ok(HttpResponse::Ok().json(api_keys))
+}
+
+/// Revoke api key. Key is rejected by the next request that uses it
+#[delete("/api-keys/{api_key_id}")]
+async fn revoke_api_key(
+    db: web::Data<Arc<Database>>,
+    api_key_id: web::Path<String>,
+) -> ApiResult<HttpResponse> {
+    let api_key_id = ObjectId::parse_str(api_key_id.as_str())
+        .map_err(|_| ApiError::Validation("invalid api key id!".into()))?;
+
+    info!("Revoking api key {api_key_id}...");
+    if !ApiKey::revoke(&db, api_key_id).await? {
+        return Err(ApiError::NotFound(format!(
+            "api key {api_key_id} was not found!"
+        )));
+    }
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Configure admin endpoints. Must be registered within a scope guarded by the admin role.
+pub fn admin_config(cfg: &mut web::ServiceConfig) {
+    cfg.service(set_player_roles)
+        .service(merge_players)
+        .service(create_api_key)
+        .service(get_api_keys)
+        .service(revoke_api_key);
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{http::StatusCode, test, App};
+    use mongodb::Client;
+
+    use super::*;
+
+    /// Call `/admin` endpoints. Requests are expected to be rejected before reaching the database.
+    async fn call_admin_api(req: test::TestRequest) -> StatusCode {
+        // client connects lazily, so nothing has to listen on this port
+        let mongo_database = Client::with_uri_str("mongodb://localhost:1")
+            .await
+            .unwrap()
+            .database("test_db");
+
+        let uut_app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(Arc::new(mongo_database)))
+                .service(web::scope("/admin").configure(admin_config)),
+        )
+        .await;
+
+        test::call_service(&uut_app, req.to_request())
+            .await
+            .status()
+    }
+
+    #[actix_web::test]
+    async fn will_return_400_on_zero_api_key_expiration() {
+        let actual_status = call_admin_api(
+            test::TestRequest::post()
+                .uri("/admin/api-keys")
+                .set_json(serde_json::json!({ "name": "data pipeline", "expires_in_days": 0 })),
+        )
+        .await;
+
+        assert_eq!(StatusCode::BAD_REQUEST, actual_status);
+    }
+
+    #[actix_web::test]
+    async fn will_return_400_on_out_of_range_api_key_expiration() {
+        let actual_status =
+            call_admin_api(test::TestRequest::post().uri("/admin/api-keys").set_json(
+                serde_json::json!({ "name": "data pipeline", "expires_in_days": 4_000_000_000u32 }),
+            ))
+            .await;
+
+        assert_eq!(StatusCode::BAD_REQUEST, actual_status);
+    }
+}
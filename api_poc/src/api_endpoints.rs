@@ -5,26 +5,96 @@ use actix_web::{
     web::{self, ReqData},
     HttpResponse, Responder,
 };
-use bson::doc;
+use bson::{doc, oid::ObjectId, DateTime};
+use chrono::Utc;
 use log::{error, info};
 use mongodb::Database;
+use serde::{Deserialize, Serialize};
 
 use crate::{
-    auth::token_service::UserClaims,
-    game::{license_plates::SpottedPlate, score_calculator::GameScoreResult},
-    AppState,
+    admin_endpoints,
+    api_error::{ApiError, ApiResult},
+    app_config::AppConfig,
+    auth::{
+        authenticated_player::{AuthenticatedPlayer, ExistingPlayer},
+        identity_provider::get_default_provider_name,
+        refresh_token::RefreshToken,
+        role_authorization::{RequireRole, ADMIN_ROLE, GUEST_ROLE},
+        token_revocation::{get_subject_revocation_id, RevokedToken},
+        token_service::UserClaims,
+    },
+    game::{
+        license_plates::SpottedPlate,
+        player::{Player, RefreshTokenRotation, GUEST_PROVIDER_NAME},
+        score_calculator::GameScoreResult,
+    },
+    game_endpoints, player_endpoints, AppState,
 };
 
+#[derive(Deserialize)]
+struct TokenRequest {
+    /// Identity provider that issued the authorization code
+    #[serde(default = "get_default_provider_name")]
+    provider: String,
+    /// Authorization code obtained by the client, or device secret for guest sign in
+    #[serde(alias = "device_secret")]
+    authorization_code: String,
+}
+
+#[derive(Deserialize)]
+struct RefreshTokenRequest {
+    refresh_token: String,
+}
+
+/// Api tokens issued to the player
+#[derive(Serialize)]
+struct TokenResponse {
+    access_token: String,
+    /// Single-use token for obtaining new api tokens. Rotated on every refresh
+    refresh_token: String,
+}
+
+fn get_refresh_token_exp(config: &AppConfig) -> DateTime {
+    let refresh_token_lifetime = chrono::Duration::days(config.refresh_token_lifetime_days as i64);
+
+    DateTime::from_millis((Utc::now() + refresh_token_lifetime).timestamp_millis())
+}
+
+async fn create_token_response(
+    data: &AppState,
+    player: &Player,
+    refresh_token: &RefreshToken,
+) -> ApiResult<TokenResponse> {
+    let mut roles: Vec<String> = player.roles.iter().cloned().collect();
+
+    // guest role is derived from identity, so upgraded guests lose it on next token
+    if player.is_guest() {
+        roles.push(GUEST_ROLE.into());
+    }
+
+    // api tokens are issued for players so game endpoints can rely on subject being a player id
+    let access_token = data
+        .token_service
+        .generate_token(&player.id.to_hex(), &player.name, &roles)
+        .await
+        .map_err(|err| ApiError::Internal(format!("failed to generate token: {err}")))?;
+
+    Ok(TokenResponse {
+        access_token: access_token.access_token,
+        refresh_token: refresh_token.as_str().into(),
+    })
+}
+
 #[get("/hello/{name}")]
 async fn hello(
     data: web::Data<Arc<AppState>>,
     db: web::Data<Arc<Database>>,
     name: web::Path<String>,
-    claims: ReqData<UserClaims>,
-) -> impl Responder {
+    player: AuthenticatedPlayer,
+) -> ApiResult<HttpResponse> {
     let app_name = &data.config.appname;
 
-    let this_user_id = &claims.sub;
+    let this_player_name = &player.name;
 
     let command = doc! {
         "find": "dummy_collection", // This collection does not need to exist
@@ -33,57 +103,278 @@ async fn hello(
         "limit": 1                  // Limit to 1 result for simplicity
     };
 
-    let db_result = db.run_command(command).await.unwrap();
+    let db_result = db.run_command(command).await?;
 
-    let hello_message = format!("Hello {name} from {this_user_id} and {app_name}.");
+    let hello_message = format!("Hello {name} from {this_player_name} and {app_name}.");
 
     let response = (hello_message, db_result);
 
-    HttpResponse::Ok().json(response)
+    Ok(HttpResponse::Ok().json(response))
 }
 
-/// Generate access token
-/// For the purposes of POC this endpoint will accept and validate a subject string in memory.
-///
-/// Actual implementation will use Google authorization code to validate the identity,
-/// create (or retrieve if exist) a player record from db, and only then generate API access token.
+/// Generate access token.
+/// Exchanges identity provider authorization code for a validated ID token,
+/// creates (or retrieves if exist) a player record for that identity, and issues a token for that player.
 #[post("/token")]
 async fn generate_token(
-    req_body: web::Json<String>,
+    req_body: web::Json<TokenRequest>,
     data: web::Data<Arc<AppState>>,
-) -> impl Responder {
-    let subject = req_body.into_inner();
+    db: web::Data<Arc<Database>>,
+) -> ApiResult<HttpResponse> {
+    let identity_provider = data.identity_providers.get_provider(&req_body.provider)?;
+    let provider_name = identity_provider.get_provider_name();
+
+    let identity = identity_provider
+        .get_identity(&req_body.authorization_code)
+        .await
+        .inspect_err(|err| error!("failed to establish {provider_name} player identity: {err}"))?;
+
+    let existing_player =
+        Player::get_player_by_existing_identity(&db, provider_name, &identity.sub).await?;
+
+    let player = match existing_player {
+        Some(player) => player,
+        None => {
+            info!("Creating new player for {provider_name} identity...");
+
+            let created_player = Player::create_from_external_identity(
+                &db,
+                identity.get_display_name(),
+                provider_name,
+                &identity.sub,
+                "",
+                DateTime::now(),
+            )
+            .await;
+
+            match created_player {
+                Ok(player) => player,
+                // concurrent sign in with the same identity (e.g. a retry) created the player first
+                Err(ApiError::Conflict(conflict)) => {
+                    Player::get_player_by_existing_identity(&db, provider_name, &identity.sub)
+                        .await?
+                        .ok_or(ApiError::Conflict(conflict))?
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    };
+
+    issue_new_tokens(&data, &db, &player).await
+}
+
+/// Sign in response. New sign in starts new refresh token family
+async fn issue_new_tokens(
+    data: &AppState,
+    mongo_database: &Database,
+    player: &Player,
+) -> ApiResult<HttpResponse> {
+    let refresh_token = RefreshToken::new(player.id);
+
+    Player::start_refresh_token_family(
+        mongo_database,
+        player.id,
+        &refresh_token.get_hash(),
+        get_refresh_token_exp(&data.config),
+    )
+    .await?;
+
+    Ok(HttpResponse::Ok().json(create_token_response(data, player, &refresh_token).await?))
+}
+
+/// Revoke presented access token on this instance and in the db
+async fn revoke_access_token(
+    data: &AppState,
+    mongo_database: &Database,
+    claims: &UserClaims,
+) -> ApiResult<()> {
+    let token_exp = DateTime::from_millis(claims.exp as i64 * 1000);
+
+    RevokedToken::revoke(mongo_database, &claims.jti, token_exp).await?;
+    data.revoked_tokens.insert(&claims.jti, token_exp)
+}
+
+/// Revoke all access tokens of deleted players, so tokens can't outlive the player.
+/// Revocation is kept for the token lifetime, since older tokens are expired anyway.
+pub async fn revoke_player_tokens(
+    data: &AppState,
+    mongo_database: &Database,
+    player_ids: &[ObjectId],
+) -> ApiResult<()> {
+    let revoked_until = DateTime::from_millis(
+        (Utc::now() + chrono::Duration::minutes(data.config.token_lifetime_min as i64))
+            .timestamp_millis(),
+    );
+
+    for player_id in player_ids {
+        let revocation_id = get_subject_revocation_id(&player_id.to_hex());
 
-    // validate request
-    if data.config.allowed_subj != subject {
-        error!("subject is not authorized!");
-        return HttpResponse::Unauthorized().finish();
+        RevokedToken::revoke(mongo_database, &revocation_id, revoked_until).await?;
+        data.revoked_tokens.insert(&revocation_id, revoked_until)?;
     }
 
-    let token_result = data.token_service.generate_token(&subject);
+    Ok(())
+}
+
+/// Upgrade guest player to a full provider identity.
+/// Guest games are kept. If the identity already has a player, guest is merged into that player.
+/// Guest access token is revoked, and tokens for the upgraded player are issued.
+#[post("/token/upgrade")]
+async fn upgrade_guest_token(
+    req_body: web::Json<TokenRequest>,
+    data: web::Data<Arc<AppState>>,
+    db: web::Data<Arc<Database>>,
+    claims: ReqData<UserClaims>,
+    ExistingPlayer(guest): ExistingPlayer,
+) -> ApiResult<HttpResponse> {
+    if !guest.is_guest() {
+        return Err(ApiError::Conflict("player is not a guest!".into()));
+    }
+
+    if req_body.provider == GUEST_PROVIDER_NAME {
+        return Err(ApiError::Validation(
+            "guest can only be upgraded to a full identity provider!".into(),
+        ));
+    }
+
+    let identity_provider = data.identity_providers.get_provider(&req_body.provider)?;
+    let provider_name = identity_provider.get_provider_name();
+
+    let identity = identity_provider
+        .get_identity(&req_body.authorization_code)
+        .await
+        .inspect_err(|err| error!("failed to establish {provider_name} player identity: {err}"))?;
 
-    match token_result {
-        Ok(token) => HttpResponse::Ok().json(token),
-        Err(err) => {
-            error!("failed to generate token {}", err);
-            HttpResponse::Unauthorized().finish()
+    let existing_player =
+        Player::get_player_by_existing_identity(&db, provider_name, &identity.sub).await?;
+
+    let upgraded_player = match existing_player {
+        Some(existing_player) => {
+            info!(
+                "Merging guest {} into {provider_name} player {}...",
+                guest.id, existing_player.id
+            );
+
+            let merged_player = Player::merge_players(&db, existing_player.id, guest.id).await?;
+            // guest may still be signed in on other devices
+            revoke_player_tokens(&data, &db, &[guest.id]).await?;
+
+            merged_player
+        }
+        None => {
+            info!(
+                "Upgrading guest {} to {provider_name} identity...",
+                guest.id
+            );
+
+            Player::upgrade_guest(
+                &db,
+                guest.id,
+                identity.get_display_name(),
+                provider_name,
+                &identity.sub,
+            )
+            .await?
+            .ok_or_else(|| ApiError::Conflict("player is not a guest!".into()))?
+        }
+    };
+
+    revoke_access_token(&data, &db, &claims).await?;
+
+    issue_new_tokens(&data, &db, &upgraded_player).await
+}
+
+/// Exchange refresh token for new api tokens.
+/// Presented refresh token is rotated, and reusing it later revokes all player refresh tokens.
+#[post("/token/refresh")]
+async fn refresh_api_token(
+    req_body: web::Json<RefreshTokenRequest>,
+    data: web::Data<Arc<AppState>>,
+    db: web::Data<Arc<Database>>,
+) -> ApiResult<HttpResponse> {
+    let presented_token = RefreshToken::parse(&req_body.refresh_token)?;
+    let new_token = RefreshToken::new(presented_token.player_id);
+
+    let rotation = Player::rotate_refresh_token(
+        &db,
+        presented_token.player_id,
+        &presented_token.get_hash(),
+        &new_token.get_hash(),
+        get_refresh_token_exp(&data.config),
+    )
+    .await?;
+
+    match rotation {
+        RefreshTokenRotation::Rotated => {
+            // player is re-read so role changes are applied on refresh
+            let player = Player::get_player_by_id(&db, presented_token.player_id)
+                .await?
+                .ok_or_else(|| ApiError::Unauthorized("refresh token is invalid!".into()))?;
+
+            Ok(HttpResponse::Ok().json(create_token_response(&data, &player, &new_token).await?))
+        }
+        RefreshTokenRotation::Reused => {
+            error!(
+                "refresh token reuse detected for player {}! Token family was revoked.",
+                presented_token.player_id
+            );
+            Err(ApiError::Unauthorized("refresh token was revoked!".into()))
+        }
+        RefreshTokenRotation::Invalid => {
+            Err(ApiError::Unauthorized("refresh token is invalid!".into()))
         }
     }
 }
 
+/// Revoke current access token, and all player refresh tokens.
+/// Other api instances reject the token after their next revoked tokens sync.
+#[post("/logout")]
+async fn logout(
+    data: web::Data<Arc<AppState>>,
+    db: web::Data<Arc<Database>>,
+    claims: ReqData<UserClaims>,
+    player: AuthenticatedPlayer,
+) -> ApiResult<HttpResponse> {
+    revoke_access_token(&data, &db, &claims).await?;
+
+    Player::revoke_refresh_token(&db, player.id).await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
 #[post("/calc_score")]
-async fn calc_score(req_body: web::Json<Vec<SpottedPlate>>) -> impl Responder {
+async fn calc_score(
+    req_body: web::Json<Vec<SpottedPlate>>,
+    data: web::Data<Arc<AppState>>,
+) -> impl Responder {
     info!("Calculating score...");
     let spotted_plates = req_body.into_inner();
 
-    let game_score = GameScoreResult::new(&spotted_plates);
+    let game_score = GameScoreResult::new(&spotted_plates, &data.achievements);
 
     HttpResponse::Ok().json(game_score)
 }
 
 /// Configure `/api` endpoints.
 pub fn api_config(cfg: &mut web::ServiceConfig) {
-    cfg.service(hello)
-        .service(calc_score)
-        .service(generate_token);
+    // malformed json bodies and path segments are reported with the same error body as other client errors
+    cfg.app_data(web::JsonConfig::default().error_handler(|err, _| {
+        ApiError::Validation(format!("invalid request body: {err}")).into()
+    }))
+    .app_data(web::PathConfig::default().error_handler(|err, _| {
+        ApiError::Validation(format!("invalid request path: {err}")).into()
+    }))
+    .service(hello)
+    .service(calc_score)
+    .service(generate_token)
+    .service(refresh_api_token)
+    .service(upgrade_guest_token)
+    .service(logout)
+    .configure(game_endpoints::game_config)
+    .configure(player_endpoints::player_config)
+    .service(
+        web::scope("/admin")
+            .wrap(RequireRole::new(ADMIN_ROLE))
+            .configure(admin_endpoints::admin_config),
+    );
 }
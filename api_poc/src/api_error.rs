@@ -0,0 +1,210 @@
+use std::fmt::{self, Display};
+
+use actix_web::{
+    http::{header::RETRY_AFTER, StatusCode},
+    HttpResponse, ResponseError,
+};
+use log::error;
+use mongodb::error::{ErrorKind, WriteFailure};
+use serde::Serialize;
+
+/// Mongo error code returned when unique index constraint is violated
+const DUPLICATE_KEY_ERROR_CODE: i32 = 11000;
+
+/// Crate-wide api error.
+/// Client errors carry a message that is safe to return to the caller.
+/// Server errors are logged and returned with a generic message so internals don't leak.
+#[derive(Debug)]
+pub enum ApiError {
+    /// Request is malformed or failed validation
+    Validation(String),
+    NotFound(String),
+    /// Request conflicts with the current state of the resource
+    Conflict(String),
+    Unauthorized(String),
+    Forbidden(String),
+    /// Client exceeded its rate limit. Carries seconds until the next request is allowed
+    TooManyRequests(u64),
+    Internal(String),
+    Database(mongodb::error::Error),
+}
+
+pub type ApiResult<T> = Result<T, ApiError>;
+
+/// Error response body. See [RFC 7807](https://datatracker.ietf.org/doc/html/rfc7807)
+#[derive(Serialize)]
+struct ProblemDetails {
+    #[serde(rename = "type")]
+    problem_type: &'static str,
+    title: &'static str,
+    status: u16,
+    detail: String,
+}
+
+impl Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Validation(message)
+            | Self::NotFound(message)
+            | Self::Conflict(message)
+            | Self::Unauthorized(message)
+            | Self::Forbidden(message)
+            | Self::Internal(message) => write!(f, "{message}"),
+            Self::TooManyRequests(retry_after_sec) => {
+                write!(
+                    f,
+                    "rate limit exceeded, retry in {retry_after_sec} seconds!"
+                )
+            }
+            Self::Database(err) => write!(f, "database error: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+impl ResponseError for ApiError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            Self::Validation(_) => StatusCode::BAD_REQUEST,
+            Self::NotFound(_) => StatusCode::NOT_FOUND,
+            Self::Conflict(_) => StatusCode::CONFLICT,
+            Self::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            Self::Forbidden(_) => StatusCode::FORBIDDEN,
+            Self::TooManyRequests(_) => StatusCode::TOO_MANY_REQUESTS,
+            Self::Internal(_) | Self::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let status = self.status_code();
+
+        let detail = if status.is_server_error() {
+            error!("request failed with {status}: {self}");
+            String::from("internal server error")
+        } else {
+            self.to_string()
+        };
+
+        let problem = ProblemDetails {
+            problem_type: "about:blank",
+            title: status.canonical_reason().unwrap_or_default(),
+            status: status.as_u16(),
+            detail,
+        };
+
+        let mut response = HttpResponse::build(status);
+
+        if let Self::TooManyRequests(retry_after_sec) = self {
+            response.insert_header((RETRY_AFTER, retry_after_sec.to_string()));
+        }
+
+        response
+            .content_type("application/problem+json")
+            .json(problem)
+    }
+}
+
+impl From<mongodb::error::Error> for ApiError {
+    fn from(err: mongodb::error::Error) -> Self {
+        match err.kind.as_ref() {
+            ErrorKind::Write(WriteFailure::WriteError(write_error))
+                if write_error.code == DUPLICATE_KEY_ERROR_CODE =>
+            {
+                Self::Conflict(String::from("resource already exists!"))
+            }
+            _ => Self::Database(err),
+        }
+    }
+}
+
+impl From<bson::ser::Error> for ApiError {
+    fn from(err: bson::ser::Error) -> Self {
+        Self::Internal(format!("failed to serialize bson: {err}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::body::MessageBody;
+
+    use super::*;
+
+    #[test]
+    fn will_map_errors_to_status_codes() {
+        let actual_statuses: Vec<StatusCode> = [
+            ApiError::Validation("validation".into()),
+            ApiError::NotFound("not found".into()),
+            ApiError::Conflict("conflict".into()),
+            ApiError::Unauthorized("unauthorized".into()),
+            ApiError::Forbidden("forbidden".into()),
+            ApiError::TooManyRequests(1),
+            ApiError::Internal("internal".into()),
+        ]
+        .iter()
+        .map(ResponseError::status_code)
+        .collect();
+
+        assert_eq!(
+            vec![
+                StatusCode::BAD_REQUEST,
+                StatusCode::NOT_FOUND,
+                StatusCode::CONFLICT,
+                StatusCode::UNAUTHORIZED,
+                StatusCode::FORBIDDEN,
+                StatusCode::TOO_MANY_REQUESTS,
+                StatusCode::INTERNAL_SERVER_ERROR,
+            ],
+            actual_statuses
+        );
+    }
+
+    #[test]
+    fn will_return_problem_details_on_client_error() {
+        let uut_error = ApiError::NotFound("game was not found!".into());
+
+        let actual_response = uut_error.error_response();
+
+        assert_eq!(StatusCode::NOT_FOUND, actual_response.status());
+        assert_eq!(
+            "application/problem+json",
+            actual_response.headers().get("content-type").unwrap()
+        );
+
+        let actual_body: serde_json::Value =
+            serde_json::from_slice(&actual_response.into_body().try_into_bytes().unwrap()).unwrap();
+
+        assert_eq!(
+            serde_json::json!({
+                "type": "about:blank",
+                "title": "Not Found",
+                "status": 404,
+                "detail": "game was not found!",
+            }),
+            actual_body
+        );
+    }
+
+    #[test]
+    fn will_hide_details_on_server_error() {
+        let uut_error = ApiError::Internal("connection string is invalid".into());
+
+        let actual_response = uut_error.error_response();
+
+        let actual_body: serde_json::Value =
+            serde_json::from_slice(&actual_response.into_body().try_into_bytes().unwrap()).unwrap();
+
+        assert_eq!(500, actual_body["status"]);
+        assert_eq!("internal server error", actual_body["detail"]);
+    }
+
+    #[test]
+    fn will_return_retry_after_on_too_many_requests() {
+        let uut_error = ApiError::TooManyRequests(42);
+
+        let actual_response = uut_error.error_response();
+
+        assert_eq!(StatusCode::TOO_MANY_REQUESTS, actual_response.status());
+        assert_eq!("42", actual_response.headers().get(RETRY_AFTER).unwrap());
+    }
+}
@@ -1,5 +1,6 @@
 use config::{Config, ConfigError, Environment};
 use dotenvy::dotenv;
+use jsonwebtoken::Algorithm;
 use log::{info, warn};
 use serde::Deserialize;
 
@@ -10,9 +11,40 @@ pub struct AppConfig {
     pub appname: String,
     pub host_ip: String,
     pub port: u16,
+    /// Api token signing algorithm. HS256, RS256, and ES256 are supported
+    pub jwt_algorithm: Algorithm,
+    /// Shared secret for HS256 tokens
     pub jwt_signing_key: String,
-    pub allowed_subj: String,
+    /// PKCS#8 private key PEM file for RS256 and ES256 tokens
+    pub jwt_private_key_file: Option<String>,
+    /// Previous signing key settings. Tokens signed with the previous key are accepted until `jwt_previous_key_accepted_until`
+    pub jwt_previous_algorithm: Option<Algorithm>,
+    pub jwt_previous_signing_key: Option<String>,
+    pub jwt_previous_private_key_file: Option<String>,
+    /// RFC 3339 time the previous signing key is accepted until, e.g. `2024-05-01T12:00:00Z`.
+    /// Absolute time, so restarts during the rotation don't extend the previous key lifetime
+    pub jwt_previous_key_accepted_until: Option<String>,
+    /// Google OpenID Connect provider used to establish player identity
+    pub google: OidcProviderConfig,
+    /// Sign in with Apple provider. Disabled when not set
+    pub apple: Option<AppleProviderConfig>,
+    /// Generic OpenID Connect provider. Disabled when not set.
+    /// Provider urls must be set explicitly since they default to Google urls
+    pub oidc: Option<OidcProviderConfig>,
+    /// Provider name stored with identities from the generic OpenID Connect provider
+    pub oidc_provider_name: String,
     pub token_lifetime_min: u32,
+    pub refresh_token_lifetime_days: u32,
+    /// Optional achievement definitions JSON file. Built-in definitions are used when not set
+    pub achievements_file: Option<String>,
+    /// Max time for each readiness check component
+    pub health_check_timeout_ms: u64,
+    /// How often revoked tokens are re-read from the database, so tokens revoked by other instances are rejected
+    pub revoked_tokens_sync_interval_sec: u64,
+    /// Guest players inactive for longer are purged. Their games are handed over to other participants, or deleted
+    pub guest_retention_days: u64,
+    /// Request rate limits for anonymous and authenticated clients
+    pub rate_limit: RateLimitConfig,
 }
 
 impl Default for AppConfig {
@@ -24,13 +56,139 @@ impl Default for AppConfig {
             appname: String::from("game api"),
             host_ip: String::from("0.0.0.0"),
             port: 8000,
+            jwt_algorithm: Algorithm::HS256,
             jwt_signing_key: String::default(),
-            allowed_subj: String::default(),
+            jwt_private_key_file: None,
+            jwt_previous_algorithm: None,
+            jwt_previous_signing_key: None,
+            jwt_previous_private_key_file: None,
+            jwt_previous_key_accepted_until: None,
+            google: OidcProviderConfig::default(),
+            apple: None,
+            oidc: None,
+            oidc_provider_name: String::from("oidc"),
             token_lifetime_min: 20,
+            refresh_token_lifetime_days: 30,
+            achievements_file: None,
+            health_check_timeout_ms: 2000,
+            revoked_tokens_sync_interval_sec: 30,
+            guest_retention_days: 30,
+            rate_limit: RateLimitConfig::default(),
         }
     }
 }
 
+/// OpenID Connect provider settings for the authorization code flow.
+/// Provider urls are configurable so tests can use a stub provider.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct OidcProviderConfig {
+    pub client_id: String,
+    pub client_secret: String,
+    /// Must match redirect uri used by the client to obtain authorization code
+    pub redirect_uri: String,
+    /// Expected ID token issuer
+    pub issuer: String,
+    pub token_url: String,
+    pub jwks_url: String,
+}
+
+impl Default for OidcProviderConfig {
+    fn default() -> Self {
+        Self {
+            client_id: String::default(),
+            client_secret: String::default(),
+            // google identity services popup flow
+            redirect_uri: String::from("postmessage"),
+            issuer: String::from("https://accounts.google.com"),
+            token_url: String::from("https://oauth2.googleapis.com/token"),
+            jwks_url: String::from("https://www.googleapis.com/oauth2/v3/certs"),
+        }
+    }
+}
+
+/// Sign in with Apple settings.
+/// Apple client secret is a short-lived JWT signed with the private key registered with Apple.
+/// See [Apple docs](https://developer.apple.com/documentation/accountorganizationaldatasharing/creating-a-client-secret)
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct AppleProviderConfig {
+    /// Services id
+    pub client_id: String,
+    pub team_id: String,
+    /// Private key id
+    pub key_id: String,
+    /// PKCS#8 private key PEM file downloaded from Apple
+    pub private_key_file: String,
+    pub redirect_uri: String,
+    pub issuer: String,
+    pub token_url: String,
+    pub jwks_url: String,
+}
+
+impl Default for AppleProviderConfig {
+    fn default() -> Self {
+        Self {
+            client_id: String::default(),
+            team_id: String::default(),
+            key_id: String::default(),
+            private_key_file: String::default(),
+            redirect_uri: String::default(),
+            issuer: String::from("https://appleid.apple.com"),
+            token_url: String::from("https://appleid.apple.com/auth/token"),
+            jwks_url: String::from("https://appleid.apple.com/auth/keys"),
+        }
+    }
+}
+
+/// Token bucket rate limiting.
+/// Anonymous requests are limited per client ip, authenticated requests per token subject.
+/// All requests, including ones failing authentication, are also limited per client ip before authentication.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct RateLimitConfig {
+    pub enabled: bool,
+    /// Keep buckets in mongo so limits hold across api instances. In-memory buckets are per instance
+    pub use_mongo_store: bool,
+    /// Use client ip from `Forwarded`/`X-Forwarded-For` headers. Enable only behind a trusted reverse proxy
+    pub trust_forwarded_for: bool,
+    pub anonymous: RateLimitBucketConfig,
+    pub authenticated: RateLimitBucketConfig,
+    /// Checked before authentication, so it must allow for several players behind the same ip
+    pub client_ip: RateLimitBucketConfig,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            use_mongo_store: false,
+            trust_forwarded_for: false,
+            // token endpoints are the only anonymous endpoints that do real work
+            anonymous: RateLimitBucketConfig {
+                capacity: 10,
+                refill_per_min: 10,
+            },
+            authenticated: RateLimitBucketConfig {
+                capacity: 60,
+                refill_per_min: 120,
+            },
+            client_ip: RateLimitBucketConfig {
+                capacity: 120,
+                refill_per_min: 240,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RateLimitBucketConfig {
+    /// Max burst of requests
+    pub capacity: u32,
+    /// Requests added back to the bucket every minute
+    pub refill_per_min: u32,
+}
+
 impl AppConfig {
     pub fn build_config() -> Result<Self, ConfigError> {
         // if .env file is available, parse it, and load parsed values as env vars
@@ -42,10 +200,115 @@ impl AppConfig {
 
         // Add in settings from the environment (with a prefix of APP)
         // E.g., `APP_DATABASE_URL` would set the `database_url`
+        // Nested settings use double underscore, e.g. `APP_GOOGLE__CLIENT_ID` would set the `google.client_id`
         let config = Config::builder()
-            .add_source(Environment::with_prefix("APP").try_parsing(true))
+            .add_source(
+                Environment::with_prefix("APP")
+                    .prefix_separator("_")
+                    .separator("__")
+                    .try_parsing(true),
+            )
             .build()?;
 
-        config.try_deserialize()
+        let app_config: Self = config.try_deserialize()?;
+        app_config.validate()?;
+
+        Ok(app_config)
+    }
+
+    /// Reject settings that would only fail later, once the api is already running
+    fn validate(&self) -> Result<(), ConfigError> {
+        if self.revoked_tokens_sync_interval_sec == 0 {
+            return Err(ConfigError::Message(
+                "revoked_tokens_sync_interval_sec must be greater than 0!".into(),
+            ));
+        }
+
+        // zero retention would purge guests right after they sign in, and huge retention overflows the purge cutoff date
+        let guest_retention_cutoff = i64::try_from(self.guest_retention_days)
+            .ok()
+            .and_then(chrono::Duration::try_days)
+            .and_then(|guest_retention| chrono::Utc::now().checked_sub_signed(guest_retention));
+
+        if self.guest_retention_days == 0 || guest_retention_cutoff.is_none() {
+            return Err(ConfigError::Message(
+                "guest_retention_days must be greater than 0 and within the supported date range!"
+                    .into(),
+            ));
+        }
+
+        let rate_limits = [
+            ("anonymous", &self.rate_limit.anonymous),
+            ("authenticated", &self.rate_limit.authenticated),
+            ("client_ip", &self.rate_limit.client_ip),
+        ];
+
+        // empty bucket would never refill, and zero refill rate divides by zero
+        for (bucket_name, bucket) in rate_limits {
+            if bucket.capacity == 0 || bucket.refill_per_min == 0 {
+                return Err(ConfigError::Message(format!(
+                    "rate_limit.{bucket_name} capacity and refill_per_min must be at least 1!"
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn will_accept_default_config() {
+        assert!(AppConfig::default().validate().is_ok());
+    }
+
+    #[test]
+    fn will_reject_zero_revoked_tokens_sync_interval() {
+        let uut_config = AppConfig {
+            revoked_tokens_sync_interval_sec: 0,
+            ..AppConfig::default()
+        };
+
+        assert_eq!(
+            "revoked_tokens_sync_interval_sec must be greater than 0!",
+            uut_config.validate().unwrap_err().to_string()
+        );
+    }
+
+    #[test]
+    fn will_reject_out_of_range_guest_retention() {
+        for guest_retention_days in [0, 1_000_000_000, u64::MAX] {
+            let uut_config = AppConfig {
+                guest_retention_days,
+                ..AppConfig::default()
+            };
+
+            assert_eq!(
+                "guest_retention_days must be greater than 0 and within the supported date range!",
+                uut_config.validate().unwrap_err().to_string()
+            );
+        }
+    }
+
+    #[test]
+    fn will_reject_zero_rate_limit_refill() {
+        let uut_config = AppConfig {
+            rate_limit: RateLimitConfig {
+                authenticated: RateLimitBucketConfig {
+                    capacity: 10,
+                    refill_per_min: 0,
+                },
+                ..RateLimitConfig::default()
+            },
+            ..AppConfig::default()
+        };
+
+        assert_eq!(
+            "rate_limit.authenticated capacity and refill_per_min must be at least 1!",
+            uut_config.validate().unwrap_err().to_string()
+        );
     }
 }
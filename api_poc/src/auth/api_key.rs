@@ -0,0 +1,178 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use bson::{doc, oid::ObjectId, DateTime};
+use futures_util::TryStreamExt;
+use mongodb::{Collection, Database};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::api_error::ApiResult;
+
+/// Header carrying the api key. Accepted instead of a bearer token
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// Subject prefix of api key claims. Api key subject is never a player id, so player endpoints reject api keys
+pub const API_KEY_SUBJECT_PREFIX: &str = "api_key:";
+
+/// Number of random bytes in the api key secret
+const API_KEY_SECRET_LEN: usize = 32;
+
+/// Api key for services and tooling that call the api without a player.
+/// Key is in `{key id}.{random secret}` format, same as refresh tokens.
+/// Only the key hash is persisted, so plaintext key is returned once at creation.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct ApiKey {
+    #[serde(rename = "_id")]
+    pub id: ObjectId,
+    /// Key owner, e.g. `data pipeline`
+    pub name: String,
+    pub key_hash: String,
+    /// Granted scopes. Checked by role guards the same way as player roles
+    pub scopes: Vec<String>,
+    pub date_created: DateTime,
+    /// Key never expires when not set
+    pub expires_at: Option<DateTime>,
+    /// Revoked keys are kept, so key usage can still be traced back to the owner
+    pub date_revoked: Option<DateTime>,
+}
+
+/// SHA-256 hash of the api key. Api keys are never persisted as is
+fn get_key_hash(api_key: &str) -> String {
+    format!("{:x}", Sha256::digest(api_key.as_bytes()))
+}
+
+/// Key id of a well-formed api key
+fn parse_key_id(api_key: &str) -> Option<ObjectId> {
+    let (key_id, secret) = api_key.split_once('.')?;
+
+    let is_valid_secret = URL_SAFE_NO_PAD
+        .decode(secret)
+        .is_ok_and(|secret| secret.len() == API_KEY_SECRET_LEN);
+
+    if !is_valid_secret {
+        return None;
+    }
+
+    ObjectId::parse_str(key_id).ok()
+}
+
+impl ApiKey {
+    pub fn get_api_key_collection(mongo_database: &Database) -> Collection<ApiKey> {
+        mongo_database.collection::<ApiKey>("api_keys")
+    }
+
+    /// Create new api key. Returns the key record and the plaintext key
+    pub async fn create(
+        mongo_database: &Database,
+        name: &str,
+        scopes: Vec<String>,
+        expires_at: Option<DateTime>,
+    ) -> ApiResult<(Self, String)> {
+        let id = ObjectId::new();
+
+        let mut secret = [0u8; API_KEY_SECRET_LEN];
+        rand::thread_rng().fill_bytes(&mut secret);
+
+        let plaintext_key = format!("{}.{}", id.to_hex(), URL_SAFE_NO_PAD.encode(secret));
+
+        let api_key = Self {
+            id,
+            name: name.into(),
+            key_hash: get_key_hash(&plaintext_key),
+            scopes,
+            date_created: DateTime::now(),
+            expires_at,
+            date_revoked: None,
+        };
+
+        Self::get_api_key_collection(mongo_database)
+            .insert_one(&api_key)
+            .await?;
+
+        Ok((api_key, plaintext_key))
+    }
+
+    /// Retrieve all api keys, including expired and revoked ones
+    pub async fn get_all(mongo_database: &Database) -> ApiResult<Vec<Self>> {
+        let api_keys = Self::get_api_key_collection(mongo_database)
+            .find(doc! {})
+            .sort(doc! { "date_created": 1 })
+            .await?
+            .try_collect()
+            .await?;
+
+        Ok(api_keys)
+    }
+
+    /// Retrieve api key record for a presented key.
+    /// Returns `None` for malformed, unknown, expired, and revoked keys.
+    pub async fn get_active_by_key(
+        mongo_database: &Database,
+        api_key: &str,
+    ) -> ApiResult<Option<Self>> {
+        let Some(key_id) = parse_key_id(api_key) else {
+            return Ok(None);
+        };
+
+        let active_key = Self::get_api_key_collection(mongo_database)
+            .find_one(doc! {
+                "_id": key_id,
+                "key_hash": get_key_hash(api_key),
+                "date_revoked": null,
+                "$or": [
+                    { "expires_at": null },
+                    { "expires_at": { "$gt": DateTime::now() } },
+                ],
+            })
+            .await?;
+
+        Ok(active_key)
+    }
+
+    /// Revoke api key. Revoking the same key twice keeps the original revocation date.
+    /// Returns false if the key doesn't exist.
+    pub async fn revoke(mongo_database: &Database, key_id: ObjectId) -> ApiResult<bool> {
+        let update_result = Self::get_api_key_collection(mongo_database)
+            .update_one(
+                doc! { "_id": key_id },
+                vec![doc! { "$set": {
+                    "date_revoked": { "$ifNull": ["$date_revoked", DateTime::now()] },
+                } }],
+            )
+            .await?;
+
+        Ok(update_result.matched_count > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn will_parse_key_id_of_well_formed_key() {
+        let key_id = ObjectId::new();
+        let api_key = format!(
+            "{}.{}",
+            key_id.to_hex(),
+            URL_SAFE_NO_PAD.encode([0u8; API_KEY_SECRET_LEN])
+        );
+
+        assert_eq!(Some(key_id), parse_key_id(&api_key));
+    }
+
+    #[test]
+    fn will_not_parse_malformed_key() {
+        let key_id = ObjectId::new().to_hex();
+
+        assert_eq!(None, parse_key_id("not_an_api_key"));
+        assert_eq!(None, parse_key_id(&format!("{key_id}.short")));
+        assert_eq!(
+            None,
+            parse_key_id(&format!(
+                "not_a_key_id.{}",
+                URL_SAFE_NO_PAD.encode([0u8; API_KEY_SECRET_LEN])
+            ))
+        );
+    }
+}
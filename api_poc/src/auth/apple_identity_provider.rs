@@ -0,0 +1,167 @@
+use std::error::Error;
+
+use chrono::Utc;
+use futures_util::{future::BoxFuture, FutureExt as _};
+use jsonwebtoken::{encode, Algorithm, EncodingKey, Header};
+use serde::Serialize;
+
+use crate::{
+    api_error::{ApiError, ApiResult},
+    app_config::{AppleProviderConfig, OidcProviderConfig},
+    auth::{
+        identity_provider::{IdentityProvider, APPLE_PROVIDER_NAME},
+        oidc_client::{OidcClient, OidcIdentity},
+    },
+};
+
+/// Apple client secret audience
+const APPLE_AUDIENCE: &str = "https://appleid.apple.com";
+/// Client secret is only used for a single code exchange, so it can be short-lived
+const CLIENT_SECRET_LIFETIME_SEC: i64 = 300;
+
+#[derive(Serialize)]
+struct ClientSecretClaims<'a> {
+    iss: &'a str,
+    sub: &'a str,
+    aud: &'a str,
+    iat: i64,
+    exp: i64,
+}
+
+/// Sign in with Apple provider.
+/// Apple follows the OpenID Connect authorization code flow, except the client secret is a signed JWT.
+/// Apple ID token doesn't carry the player name, so email is used as display name.
+pub struct AppleIdentityProvider {
+    oidc_client: OidcClient,
+    client_id: String,
+    team_id: String,
+    key_id: String,
+    signing_key: EncodingKey,
+}
+
+impl AppleIdentityProvider {
+    pub fn new(
+        config: &AppleProviderConfig,
+        private_key_pem: &str,
+    ) -> Result<Self, Box<dyn Error>> {
+        let oidc_config = OidcProviderConfig {
+            client_id: config.client_id.clone(),
+            client_secret: String::default(),
+            redirect_uri: config.redirect_uri.clone(),
+            issuer: config.issuer.clone(),
+            token_url: config.token_url.clone(),
+            jwks_url: config.jwks_url.clone(),
+        };
+
+        Ok(Self {
+            oidc_client: OidcClient::new(APPLE_PROVIDER_NAME, oidc_config),
+            client_id: config.client_id.clone(),
+            team_id: config.team_id.clone(),
+            key_id: config.key_id.clone(),
+            signing_key: EncodingKey::from_ec_pem(private_key_pem.as_bytes())?,
+        })
+    }
+
+    pub fn from_config(config: &AppleProviderConfig) -> Result<Self, Box<dyn Error>> {
+        let private_key_pem = std::fs::read_to_string(&config.private_key_file).map_err(|err| {
+            format!(
+                "failed to read apple private key {}: {err}",
+                config.private_key_file
+            )
+        })?;
+
+        Self::new(config, &private_key_pem)
+    }
+
+    fn create_client_secret(&self) -> ApiResult<String> {
+        let now = Utc::now().timestamp();
+
+        let claims = ClientSecretClaims {
+            iss: &self.team_id,
+            sub: &self.client_id,
+            aud: APPLE_AUDIENCE,
+            iat: now,
+            exp: now + CLIENT_SECRET_LIFETIME_SEC,
+        };
+
+        let header = Header {
+            kid: Some(self.key_id.clone()),
+            ..Header::new(Algorithm::ES256)
+        };
+
+        encode(&header, &claims, &self.signing_key)
+            .map_err(|err| ApiError::Internal(format!("failed to sign apple client secret: {err}")))
+    }
+}
+
+impl IdentityProvider for AppleIdentityProvider {
+    fn get_provider_name(&self) -> &str {
+        APPLE_PROVIDER_NAME
+    }
+
+    fn get_identity<'a>(&'a self, credential: &'a str) -> BoxFuture<'a, ApiResult<OidcIdentity>> {
+        async move {
+            let client_secret = self.create_client_secret()?;
+
+            self.oidc_client
+                .get_identity_from_code_with_secret(credential, &client_secret)
+                .await
+        }
+        .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use jsonwebtoken::{decode, decode_header, DecodingKey, Validation};
+    use serde::Deserialize;
+
+    use super::super::signing_key::JwtSigningKey;
+    use super::*;
+
+    #[derive(Deserialize)]
+    struct ActualClientSecretClaims {
+        iss: String,
+        sub: String,
+    }
+
+    #[test]
+    fn will_sign_client_secret_for_apple() {
+        let config = AppleProviderConfig {
+            client_id: "test.services.id".into(),
+            team_id: "TEAMID".into(),
+            key_id: "KEYID".into(),
+            ..AppleProviderConfig::default()
+        };
+
+        let private_key_pem = include_str!("../../tests/fixtures/jwt_test_ec_key.pem");
+        let uut_provider = AppleIdentityProvider::new(&config, private_key_pem).unwrap();
+
+        let actual_secret = uut_provider.create_client_secret().unwrap();
+
+        let actual_header = decode_header(&actual_secret).unwrap();
+        assert_eq!(Algorithm::ES256, actual_header.alg);
+        assert_eq!(Some("KEYID".into()), actual_header.kid);
+
+        let mut validation = Validation::new(Algorithm::ES256);
+        validation.set_audience(&[APPLE_AUDIENCE]);
+
+        // signature is verified with the key's public half
+        let public_jwk = JwtSigningKey::from_ec_pem(private_key_pem)
+            .unwrap()
+            .get_public_jwk()
+            .cloned()
+            .unwrap();
+
+        let actual_claims = decode::<ActualClientSecretClaims>(
+            &actual_secret,
+            &DecodingKey::from_jwk(&public_jwk).unwrap(),
+            &validation,
+        )
+        .unwrap()
+        .claims;
+
+        assert_eq!("TEAMID", actual_claims.iss);
+        assert_eq!("test.services.id", actual_claims.sub);
+    }
+}
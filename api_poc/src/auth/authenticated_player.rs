@@ -0,0 +1,142 @@
+use std::{
+    future::{ready, Ready},
+    sync::Arc,
+};
+
+use actix_web::{dev::Payload, web::Data, FromRequest, HttpMessage, HttpRequest};
+use bson::oid::ObjectId;
+use futures_util::future::LocalBoxFuture;
+use mongodb::Database;
+
+use crate::{
+    api_error::{ApiError, ApiResult},
+    auth::{role_authorization::GUEST_ROLE, token_service::UserClaims},
+    game::player::Player,
+};
+
+/// Player established from validated token claims. Doesn't require a db round trip.
+/// Use [`ExistingPlayer`] when the handler needs the player record.
+#[derive(Debug, Clone)]
+pub struct AuthenticatedPlayer {
+    pub id: ObjectId,
+    pub name: String,
+    /// Guest tokens are limited, e.g. guests can't invite other players
+    pub is_guest: bool,
+}
+
+impl AuthenticatedPlayer {
+    fn from_claims(claims: &UserClaims) -> ApiResult<Self> {
+        // api tokens are issued for players, so subject is always a player id
+        let id = ObjectId::parse_str(&claims.sub).map_err(|_| {
+            ApiError::Unauthorized("token subject is not a valid player id!".into())
+        })?;
+
+        Ok(Self {
+            id,
+            name: claims.name.clone(),
+            is_guest: claims.roles.iter().any(|role| role == GUEST_ROLE),
+        })
+    }
+
+    /// Load player record. Tokens of deleted players are rejected
+    pub async fn get_player(&self, mongo_database: &Database) -> ApiResult<Player> {
+        Player::get_player_by_id(mongo_database, self.id)
+            .await?
+            .ok_or_else(|| ApiError::Unauthorized("token player no longer exists!".into()))
+    }
+}
+
+impl FromRequest for AuthenticatedPlayer {
+    type Error = ApiError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        // claims are added by the auth middleware
+        let authenticated_player = match req.extensions().get::<UserClaims>() {
+            Some(claims) => Self::from_claims(claims),
+            None => Err(ApiError::Unauthorized("bearer token is missing!".into())),
+        };
+
+        ready(authenticated_player)
+    }
+}
+
+/// Authenticated player record loaded from the database
+#[derive(Debug, Clone)]
+pub struct ExistingPlayer(pub Player);
+
+impl FromRequest for ExistingPlayer {
+    type Error = ApiError;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let authenticated_player = AuthenticatedPlayer::from_request(req, payload).into_inner();
+        let mongo_database = req.app_data::<Data<Arc<Database>>>().cloned();
+
+        Box::pin(async move {
+            let mongo_database = mongo_database
+                .ok_or_else(|| ApiError::Internal("database is not available!".into()))?;
+
+            let player = authenticated_player?.get_player(&mongo_database).await?;
+
+            Ok(Self(player))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::test;
+
+    use crate::test_helpers::create_test_claims;
+
+    use super::*;
+
+    #[actix_web::test]
+    async fn will_extract_player_from_claims() {
+        let player_id = ObjectId::new();
+
+        let req = test::TestRequest::default().to_http_request();
+        req.extensions_mut()
+            .insert(create_test_claims(&player_id.to_hex()));
+
+        let actual_player = AuthenticatedPlayer::extract(&req).await.unwrap();
+
+        assert_eq!(player_id, actual_player.id);
+        assert_eq!("test player", actual_player.name);
+        assert!(!actual_player.is_guest);
+    }
+
+    #[actix_web::test]
+    async fn will_extract_guest_player_from_claims() {
+        let mut claims = create_test_claims(&ObjectId::new().to_hex());
+        claims.roles = vec![GUEST_ROLE.into()];
+
+        let req = test::TestRequest::default().to_http_request();
+        req.extensions_mut().insert(claims);
+
+        let actual_player = AuthenticatedPlayer::extract(&req).await.unwrap();
+
+        assert!(actual_player.is_guest);
+    }
+
+    #[actix_web::test]
+    async fn will_return_unauthorized_on_non_player_subject() {
+        let req = test::TestRequest::default().to_http_request();
+        req.extensions_mut()
+            .insert(create_test_claims("not_a_player_id"));
+
+        let actual_err = AuthenticatedPlayer::extract(&req).await.unwrap_err();
+
+        assert!(matches!(actual_err, ApiError::Unauthorized(_)));
+    }
+
+    #[actix_web::test]
+    async fn will_return_unauthorized_on_missing_claims() {
+        let req = test::TestRequest::default().to_http_request();
+
+        let actual_err = AuthenticatedPlayer::extract(&req).await.unwrap_err();
+
+        assert!(matches!(actual_err, ApiError::Unauthorized(_)));
+    }
+}
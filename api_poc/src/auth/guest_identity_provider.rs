@@ -0,0 +1,73 @@
+use futures_util::future::{self, BoxFuture};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    api_error::{ApiError, ApiResult},
+    auth::{identity_provider::IdentityProvider, oidc_client::OidcIdentity},
+    game::player::GUEST_PROVIDER_NAME,
+};
+
+/// Min device secret length. Secret is the only guest credential, so it must not be guessable
+const MIN_DEVICE_SECRET_LEN: usize = 32;
+
+/// Guest sign in with a secret generated and kept by the client device.
+/// Only the secret hash is used as the guest identity, so the secret itself is never persisted.
+pub struct GuestIdentityProvider;
+
+impl GuestIdentityProvider {
+    fn get_guest_identity(device_secret: &str) -> ApiResult<OidcIdentity> {
+        if device_secret.len() < MIN_DEVICE_SECRET_LEN {
+            return Err(ApiError::Validation(format!(
+                "device secret must be at least {MIN_DEVICE_SECRET_LEN} characters long!"
+            )));
+        }
+
+        Ok(OidcIdentity {
+            sub: format!("{:x}", Sha256::digest(device_secret.as_bytes())),
+            name: Some("guest".into()),
+            email: None,
+        })
+    }
+}
+
+impl IdentityProvider for GuestIdentityProvider {
+    fn get_provider_name(&self) -> &str {
+        GUEST_PROVIDER_NAME
+    }
+
+    fn get_identity<'a>(&'a self, credential: &'a str) -> BoxFuture<'a, ApiResult<OidcIdentity>> {
+        Box::pin(future::ready(Self::get_guest_identity(credential)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[actix_web::test]
+    async fn will_return_same_identity_for_same_secret() {
+        let device_secret = "a".repeat(MIN_DEVICE_SECRET_LEN);
+
+        let first_identity = GuestIdentityProvider
+            .get_identity(&device_secret)
+            .await
+            .unwrap();
+        let second_identity = GuestIdentityProvider
+            .get_identity(&device_secret)
+            .await
+            .unwrap();
+
+        assert_eq!(first_identity.sub, second_identity.sub);
+        assert_ne!(device_secret, first_identity.sub);
+    }
+
+    #[actix_web::test]
+    async fn will_return_validation_error_on_short_secret() {
+        let actual_err = GuestIdentityProvider
+            .get_identity("short")
+            .await
+            .unwrap_err();
+
+        assert!(matches!(actual_err, ApiError::Validation(_)));
+    }
+}
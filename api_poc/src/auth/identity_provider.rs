@@ -0,0 +1,116 @@
+use std::{collections::HashMap, error::Error};
+
+use futures_util::future::BoxFuture;
+use log::info;
+
+use crate::{
+    api_error::{ApiError, ApiResult},
+    app_config::AppConfig,
+    auth::{
+        apple_identity_provider::AppleIdentityProvider,
+        guest_identity_provider::GuestIdentityProvider,
+        oidc_client::{OidcClient, OidcIdentity},
+    },
+};
+
+pub const GOOGLE_PROVIDER_NAME: &str = "google";
+pub const APPLE_PROVIDER_NAME: &str = "apple";
+
+/// Provider used when client doesn't specify one. Older clients only support Google
+pub fn get_default_provider_name() -> String {
+    GOOGLE_PROVIDER_NAME.into()
+}
+
+/// External identity provider used to establish player identity.
+/// Provider name and identity id map onto the player `(provider_name, provider_identity_id)` unique index.
+pub trait IdentityProvider: Send + Sync {
+    /// Provider name stored with player identity
+    fn get_provider_name(&self) -> &str;
+
+    /// Exchange provider credential (e.g. authorization code) for a verified identity
+    fn get_identity<'a>(&'a self, credential: &'a str) -> BoxFuture<'a, ApiResult<OidcIdentity>>;
+}
+
+/// Configured identity providers by name
+#[derive(Default)]
+pub struct IdentityProviderRegistry {
+    providers: HashMap<String, Box<dyn IdentityProvider>>,
+}
+
+impl IdentityProviderRegistry {
+    /// Google and guest sign in are always available. Apple and generic OIDC providers are added when configured
+    pub fn from_config(config: &AppConfig) -> Result<Self, Box<dyn Error>> {
+        let mut registry = Self::default()
+            .with_provider(OidcClient::new(GOOGLE_PROVIDER_NAME, config.google.clone()))
+            .with_provider(GuestIdentityProvider);
+
+        if let Some(apple_config) = &config.apple {
+            registry = registry.with_provider(AppleIdentityProvider::from_config(apple_config)?);
+        }
+
+        if let Some(oidc_config) = &config.oidc {
+            if registry.providers.contains_key(&config.oidc_provider_name) {
+                return Err(format!(
+                    "oidc provider name {} is already used!",
+                    config.oidc_provider_name
+                )
+                .into());
+            }
+
+            registry = registry.with_provider(OidcClient::new(
+                &config.oidc_provider_name,
+                oidc_config.clone(),
+            ));
+        }
+
+        Ok(registry)
+    }
+
+    pub fn with_provider(mut self, provider: impl IdentityProvider + 'static) -> Self {
+        info!(
+            "enabling {} identity provider",
+            provider.get_provider_name()
+        );
+
+        self.providers
+            .insert(provider.get_provider_name().into(), Box::new(provider));
+        self
+    }
+
+    pub fn get_provider(&self, provider_name: &str) -> ApiResult<&dyn IdentityProvider> {
+        self.providers
+            .get(provider_name)
+            .map(Box::as_ref)
+            .ok_or_else(|| {
+                ApiError::Validation(format!(
+                    "{provider_name} identity provider is not supported!"
+                ))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn will_register_google_provider_by_default() {
+        let uut_registry = IdentityProviderRegistry::from_config(&AppConfig::default()).unwrap();
+
+        let actual_provider = uut_registry.get_provider(GOOGLE_PROVIDER_NAME).unwrap();
+
+        assert_eq!(GOOGLE_PROVIDER_NAME, actual_provider.get_provider_name());
+    }
+
+    #[test]
+    fn will_return_validation_error_on_unknown_provider() {
+        let uut_registry = IdentityProviderRegistry::from_config(&AppConfig::default()).unwrap();
+
+        let actual_err = uut_registry
+            .get_provider(APPLE_PROVIDER_NAME)
+            .err()
+            .unwrap();
+
+        assert!(matches!(actual_err, ApiError::Validation(_)));
+    }
+}
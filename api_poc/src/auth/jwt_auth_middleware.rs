@@ -1,43 +1,96 @@
 use std::{
-    collections::HashSet,
     future::{ready, Ready},
+    rc::Rc,
     sync::Arc,
 };
 
 use actix_web::{
     body::EitherBody,
-    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
-    http::header::AUTHORIZATION,
+    dev::{forward_ready, ResourceDef, Service, ServiceRequest, ServiceResponse, Transform},
+    http::{
+        header::{ToStrError, AUTHORIZATION},
+        Method,
+    },
     web::Data,
-    Error, HttpMessage, HttpResponse,
+    Error, HttpMessage, ResponseError,
 };
 use futures_util::{future::LocalBoxFuture, FutureExt as _, TryFutureExt as _};
 use log::{error, info};
 
-use crate::AppState;
+use mongodb::Database;
+
+use crate::{
+    api_error::{ApiError, ApiResult},
+    auth::{
+        api_key::{ApiKey, API_KEY_HEADER, API_KEY_SUBJECT_PREFIX},
+        token_error::TokenError,
+        token_service::UserClaims,
+    },
+    AppState,
+};
+
+/// Route that doesn't require a bearer token.
+/// Rules are matched against the request path only, so query strings are ignored.
+/// Matching is as strict as actix routing, e.g. `/api/token/` doesn't match `/api/token` rule.
+pub struct AnonymousRoute {
+    pattern: ResourceDef,
+    /// Allowed methods. Any method is allowed when empty
+    methods: Vec<Method>,
+}
+
+impl AnonymousRoute {
+    /// Match exact path or actix-style path pattern, e.g. `/api/games/{game_id}`
+    pub fn path(pattern: &str) -> Self {
+        Self {
+            pattern: ResourceDef::new(pattern),
+            methods: vec![],
+        }
+    }
+
+    /// Match path prefix on segment boundaries, e.g. `/health` matches `/health/live` but not `/healthz`
+    pub fn prefix(prefix: &str) -> Self {
+        Self {
+            pattern: ResourceDef::prefix(prefix),
+            methods: vec![],
+        }
+    }
+
+    /// Allow anonymous access only for the given method. Can be called multiple times
+    pub fn with_method(mut self, method: Method) -> Self {
+        self.methods.push(method);
+        self
+    }
+
+    pub fn is_match(&self, req: &ServiceRequest) -> bool {
+        (self.methods.is_empty() || self.methods.contains(req.method()))
+            && self.pattern.is_match(req.path())
+    }
+}
 
 /// [Inspired by](https://github.com/actix/examples/blob/master/middleware/rate-limit/src/rate_limit.rs)
 pub struct JwtAuthentication {
-    anonymous_urls: Vec<String>,
+    anonymous_routes: Rc<Vec<AnonymousRoute>>,
 }
 
 impl JwtAuthentication {
-    pub fn new(anonymous_urls: Vec<String>) -> Self {
-        Self { anonymous_urls }
+    pub fn new(anonymous_routes: Vec<AnonymousRoute>) -> Self {
+        Self {
+            anonymous_routes: Rc::new(anonymous_routes),
+        }
     }
 }
 
 /// JWT auth middleware
 pub struct JwtAuthenticationMiddleware<S> {
-    /// The next service to call after this one
-    service: S,
-    anonymous_urls: HashSet<String>,
+    /// The next service to call after this one. Shared with the future awaiting token validation
+    service: Rc<S>,
+    anonymous_routes: Rc<Vec<AnonymousRoute>>,
 }
 
 /// JWT Auth middleware implementation
 impl<S, B> Service<ServiceRequest> for JwtAuthenticationMiddleware<S>
 where
-    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
     S::Future: 'static,
     B: 'static,
 {
@@ -52,7 +105,11 @@ where
         info!("Authenticating {}", request_url);
 
         // check if route expects anonymous auth. Use allow-list for simplicity
-        if self.anonymous_urls.contains(&request_url.to_string()) {
+        if self
+            .anonymous_routes
+            .iter()
+            .any(|anonymous_route| anonymous_route.is_match(&req))
+        {
             info!("Uri is marked for anonymous auth. Skipping auth validation...");
             return self
                 .service
@@ -66,61 +123,138 @@ where
             error!("AppState not found in app_data. TokenService is not available");
             return Box::pin(async {
                 Ok(req.into_response(
-                    HttpResponse::InternalServerError()
-                        .finish()
+                    ApiError::Internal("token service is not available!".into())
+                        .error_response()
                         .map_into_right_body(),
                 ))
             });
         }
 
-        let bearer_token = req.headers().get(AUTHORIZATION).and_then(|header_value| {
-            header_value
-                .to_str()
-                // TODO make Bearer case-insenstive
-                .map_or(None, |header_str| header_str.strip_prefix("Bearer "))
-        });
+        // app state is cloned, so token validation can be awaited after this call returns
+        let app_state = app_state.unwrap().clone();
+        let api_key = req
+            .headers()
+            .get(API_KEY_HEADER)
+            .map(|api_key| api_key.to_str().map(str::to_owned));
+        let mongo_database = req.app_data::<Data<Arc<Database>>>().cloned();
+        let bearer_token = get_bearer_token(&req).map(str::to_owned);
+        let service = self.service.clone();
+
+        Box::pin(async move {
+            // api key takes precedence, since services and tooling never have a bearer token
+            let claims = match api_key {
+                Some(api_key) => get_api_key_claims(api_key, mongo_database, &app_state)
+                    .await
+                    .map_err(Error::from),
+                None => get_bearer_claims(bearer_token, &app_state)
+                    .await
+                    .map_err(Error::from),
+            };
+
+            match claims {
+                Ok(claims) => {
+                    // add claims to request extensions so endpoints can use them to establish user context
+                    // for the time being, we don't need to query db because everything we'll need will be
+                    // stored in claims
+                    req.extensions_mut().insert(claims);
+
+                    service
+                        .call(req)
+                        .await
+                        .map(ServiceResponse::map_into_left_body)
+                }
+                Err(err) => {
+                    error!("Request credentials were rejected: {err}");
+
+                    Ok(req.into_response(err.error_response().map_into_right_body()))
+                }
+            }
+        })
+    }
+}
 
-        if bearer_token.is_none() {
-            error!("Bearer token was not found in request headers");
-            return Box::pin(async {
-                Ok(req.into_response(HttpResponse::Unauthorized().finish().map_into_right_body()))
-            });
-        };
+async fn get_bearer_claims(
+    bearer_token: Result<String, TokenError>,
+    app_state: &AppState,
+) -> Result<UserClaims, TokenError> {
+    let claims = app_state
+        .token_service
+        .get_validated_claims(&bearer_token?)
+        .await?;
+
+    // revoked tokens are checked against in-process cache to avoid db round trip on every request
+    if app_state
+        .revoked_tokens
+        .is_token_revoked(&claims.jti, &claims.sub)
+    {
+        return Err(TokenError::Revoked);
+    }
 
-        // unwrapping is safe here because we have already validated app_state and bearer_token for None
-        let claims = app_state
-            .unwrap()
-            .token_service
-            .get_validated_claims(bearer_token.unwrap());
-
-        match claims {
-            Ok(claims) => {
-                // add claims to request extensions so endpoints can use them to establish user context
-                // for the time being, we don't need to query db because everything we'll need will be
-                // stored in claims
-                req.extensions_mut().insert(claims);
-
-                self.service
-                    .call(req)
-                    .map_ok(ServiceResponse::map_into_left_body)
-                    .boxed_local()
-            }
-            Err(err) => {
-                error!("Bearer token is invalid: {err}");
+    Ok(claims)
+}
 
-                Box::pin(async {
-                    Ok(req
-                        .into_response(HttpResponse::Unauthorized().finish().map_into_right_body()))
-                })
-            }
+/// Api key is looked up on every request, so revoked keys are rejected right away.
+/// Key scopes are mapped onto roles, so role guards apply to api keys as is.
+async fn get_api_key_claims(
+    api_key: Result<String, ToStrError>,
+    mongo_database: Option<Data<Arc<Database>>>,
+    app_state: &AppState,
+) -> ApiResult<UserClaims> {
+    let invalid_key = || ApiError::Unauthorized("api key is invalid!".into());
+
+    let api_key = api_key.map_err(|_| invalid_key())?;
+    let mongo_database =
+        mongo_database.ok_or_else(|| ApiError::Internal("database is not available!".into()))?;
+
+    let api_key = ApiKey::get_active_by_key(&mongo_database, &api_key)
+        .await?
+        .ok_or_else(invalid_key)?;
+
+    Ok(UserClaims {
+        sub: format!("{API_KEY_SUBJECT_PREFIX}{}", api_key.id.to_hex()),
+        aud: app_state.config.appname.clone(),
+        iss: app_state.config.appname.clone(),
+        exp: api_key.expires_at.map_or(0, |expires_at| {
+            (expires_at.timestamp_millis() / 1000) as usize
+        }),
+        nbf: 0,
+        iat: (api_key.date_created.timestamp_millis() / 1000) as usize,
+        jti: api_key.id.to_hex(),
+        name: api_key.name,
+        roles: api_key.scopes,
+    })
+}
+
+/// Extract bearer token from the `Authorization` header. See [RFC 6750](https://datatracker.ietf.org/doc/html/rfc6750#section-2.1)
+/// Scheme is case-insensitive, and extra whitespace around scheme and token is tolerated.
+fn get_bearer_token(req: &ServiceRequest) -> Result<&str, TokenError> {
+    let header_str = req
+        .headers()
+        .get(AUTHORIZATION)
+        .ok_or(TokenError::Missing)?
+        .to_str()
+        .map_err(|_| TokenError::Malformed)?;
+
+    let mut header_parts = header_str.split_whitespace();
+
+    match (
+        header_parts.next(),
+        header_parts.next(),
+        header_parts.next(),
+    ) {
+        (Some(scheme), Some(token), None) if scheme.eq_ignore_ascii_case("bearer") => Ok(token),
+        (Some(scheme), Some(_), Some(_)) if scheme.eq_ignore_ascii_case("bearer") => {
+            Err(TokenError::Malformed)
         }
+        // empty bearer and other auth schemes carry no bearer token
+        _ => Err(TokenError::Missing),
     }
 }
 
 /// Jwt Auth middleware factory
 impl<S, B> Transform<S, ServiceRequest> for JwtAuthentication
 where
-    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
     S::Future: 'static,
     B: 'static,
 {
@@ -135,32 +269,37 @@ where
     fn new_transform(&self, service: S) -> Self::Future {
         // Return a Ready future containing the JwtAuthenticationMiddleware instance
         ready(Ok(JwtAuthenticationMiddleware {
-            service,
-            anonymous_urls: self.anonymous_urls.iter().cloned().collect(),
+            service: Rc::new(service),
+            anonymous_routes: self.anonymous_routes.clone(),
         }))
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{app_config::AppConfig, auth::token_service::JwtTokenService};
+    use crate::{
+        auth::{
+            token_revocation::get_subject_revocation_id,
+            token_service::{JwtToken, JwtTokenService, TokenService, UserClaims},
+        },
+        test_helpers::{create_test_app_state, create_test_token_service},
+    };
 
     use super::*;
     use actix_web::{
         http::{self, StatusCode},
-        test, web, App,
+        test, web, App, HttpResponse,
     };
+    use futures_util::future::BoxFuture;
+    use jsonwebtoken::jwk::JwkSet;
 
     #[actix_web::test]
     async fn will_return_401_on_missing_auth() {
-        let app_state = Arc::new(AppState {
-            token_service: Box::new(JwtTokenService::new("test key", "issuer", "audience", 1, 1)),
-            config: AppConfig::default(),
-        });
+        let app_state = Arc::new(create_test_app_state());
 
         let uut_app = test::init_service(
             App::new()
-                .wrap(JwtAuthentication::new(vec!["".into()]))
+                .wrap(JwtAuthentication::new(vec![]))
                 .app_data(web::Data::new(app_state.clone()))
                 .route("/", web::get().to(HttpResponse::Ok)),
         )
@@ -175,14 +314,11 @@ mod tests {
 
     #[actix_web::test]
     async fn will_return_401_on_bad_auth() {
-        let app_state = Arc::new(AppState {
-            token_service: Box::new(JwtTokenService::new("test key", "issuer", "audience", 1, 1)),
-            config: AppConfig::default(),
-        });
+        let app_state = Arc::new(create_test_app_state());
 
         let uut_app = test::init_service(
             App::new()
-                .wrap(JwtAuthentication::new(vec!["".into()]))
+                .wrap(JwtAuthentication::new(vec![]))
                 .app_data(web::Data::new(app_state.clone()))
                 .route("/", web::get().to(HttpResponse::Ok)),
         )
@@ -200,19 +336,17 @@ mod tests {
 
     #[actix_web::test]
     async fn will_return_200_on_valid_auth() {
-        let app_state = Arc::new(AppState {
-            token_service: Box::new(JwtTokenService::new("test key", "issuer", "audience", 1, 1)),
-            config: AppConfig::default(),
-        });
+        let app_state = Arc::new(create_test_app_state());
 
         let valid_token = app_state
             .token_service
-            .generate_token("test_subject")
+            .generate_token("test_subject", "test player", &[])
+            .await
             .unwrap();
 
         let uut_app = test::init_service(
             App::new()
-                .wrap(JwtAuthentication::new(vec!["".into()]))
+                .wrap(JwtAuthentication::new(vec![]))
                 .app_data(web::Data::new(app_state.clone()))
                 .route("/", web::get().to(HttpResponse::Ok)),
         )
@@ -234,14 +368,11 @@ mod tests {
 
     #[actix_web::test]
     async fn will_return_200_on_anonymous_auth() {
-        let app_state = Arc::new(AppState {
-            token_service: Box::new(JwtTokenService::new("test key", "issuer", "audience", 1, 1)),
-            config: AppConfig::default(),
-        });
+        let app_state = Arc::new(create_test_app_state());
 
         let uut_app = test::init_service(
             App::new()
-                .wrap(JwtAuthentication::new(vec!["/test".into()]))
+                .wrap(JwtAuthentication::new(vec![AnonymousRoute::path("/test")]))
                 .app_data(web::Data::new(app_state.clone()))
                 .route("/test", web::get().to(HttpResponse::Ok)),
         )
@@ -256,4 +387,337 @@ mod tests {
 
         assert_eq!(StatusCode::OK, actual_resp.status());
     }
+
+    #[actix_web::test]
+    async fn will_return_401_on_revoked_token() {
+        let app_state = Arc::new(create_test_app_state());
+
+        let revoked_token = app_state
+            .token_service
+            .generate_token("test_subject", "test player", &[])
+            .await
+            .unwrap();
+
+        let revoked_claims = app_state
+            .token_service
+            .get_validated_claims(&revoked_token.access_token)
+            .await
+            .unwrap();
+
+        app_state
+            .revoked_tokens
+            .insert(
+                &revoked_claims.jti,
+                bson::DateTime::from_millis(revoked_claims.exp as i64 * 1000),
+            )
+            .unwrap();
+
+        let uut_app = test::init_service(
+            App::new()
+                .wrap(JwtAuthentication::new(vec![]))
+                .app_data(web::Data::new(app_state.clone()))
+                .route("/", web::get().to(HttpResponse::Ok)),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/")
+            .insert_header((
+                http::header::AUTHORIZATION,
+                format!("Bearer {}", &revoked_token.access_token),
+            ))
+            .to_request();
+
+        let actual_resp = test::call_service(&uut_app, req).await;
+
+        assert_eq!(StatusCode::UNAUTHORIZED, actual_resp.status());
+    }
+
+    #[actix_web::test]
+    async fn will_return_401_on_token_of_revoked_subject() {
+        let app_state = Arc::new(create_test_app_state());
+
+        let revoked_token = app_state
+            .token_service
+            .generate_token("test_subject", "test player", &[])
+            .await
+            .unwrap();
+
+        let revoked_claims = app_state
+            .token_service
+            .get_validated_claims(&revoked_token.access_token)
+            .await
+            .unwrap();
+
+        app_state
+            .revoked_tokens
+            .insert(
+                &get_subject_revocation_id(&revoked_claims.sub),
+                bson::DateTime::from_millis(revoked_claims.exp as i64 * 1000),
+            )
+            .unwrap();
+
+        let uut_app = test::init_service(
+            App::new()
+                .wrap(JwtAuthentication::new(vec![]))
+                .app_data(web::Data::new(app_state.clone()))
+                .route("/", web::get().to(HttpResponse::Ok)),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/")
+            .insert_header((
+                http::header::AUTHORIZATION,
+                format!("Bearer {}", &revoked_token.access_token),
+            ))
+            .to_request();
+
+        let actual_resp = test::call_service(&uut_app, req).await;
+
+        assert_eq!(StatusCode::UNAUTHORIZED, actual_resp.status());
+    }
+
+    #[actix_web::test]
+    async fn will_return_401_on_malformed_api_key() {
+        let app_state = Arc::new(create_test_app_state());
+
+        // client connects lazily, and malformed keys are rejected before the db lookup
+        let mongo_database = mongodb::Client::with_uri_str("mongodb://localhost:27017")
+            .await
+            .unwrap()
+            .database("test_db");
+
+        let uut_app = test::init_service(
+            App::new()
+                .wrap(JwtAuthentication::new(vec![]))
+                .app_data(web::Data::new(app_state.clone()))
+                .app_data(web::Data::new(Arc::new(mongo_database)))
+                .route("/", web::get().to(HttpResponse::Ok)),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/")
+            .insert_header((API_KEY_HEADER, "not_an_api_key"))
+            .to_request();
+
+        let actual_resp = test::call_service(&uut_app, req).await;
+
+        assert_eq!(StatusCode::UNAUTHORIZED, actual_resp.status());
+    }
+
+    /// Call app that responds with 200 to any authenticated request
+    async fn call_without_token(
+        anonymous_routes: Vec<AnonymousRoute>,
+        method: Method,
+        uri: &str,
+    ) -> StatusCode {
+        let app_state = Arc::new(create_test_app_state());
+
+        let uut_app = test::init_service(
+            App::new()
+                .wrap(JwtAuthentication::new(anonymous_routes))
+                .app_data(web::Data::new(app_state.clone()))
+                .default_service(web::to(HttpResponse::Ok)),
+        )
+        .await;
+
+        let req = test::TestRequest::default()
+            .method(method)
+            .uri(uri)
+            .to_request();
+
+        test::call_service(&uut_app, req).await.status()
+    }
+
+    #[actix_web::test]
+    async fn will_ignore_query_string_on_anonymous_auth() {
+        let actual_status = call_without_token(
+            vec![AnonymousRoute::path("/api/token")],
+            Method::POST,
+            "/api/token?x=1",
+        )
+        .await;
+
+        assert_eq!(StatusCode::OK, actual_status);
+    }
+
+    #[actix_web::test]
+    async fn will_return_401_on_trailing_slash_mismatch() {
+        let actual_status = call_without_token(
+            vec![AnonymousRoute::path("/api/token")],
+            Method::POST,
+            "/api/token/",
+        )
+        .await;
+
+        assert_eq!(StatusCode::UNAUTHORIZED, actual_status);
+    }
+
+    #[actix_web::test]
+    async fn will_return_200_on_anonymous_prefix() {
+        let anonymous_routes = || vec![AnonymousRoute::prefix("/health")];
+
+        for uri in ["/health", "/health/live", "/health/ready?verbose=true"] {
+            let actual_status = call_without_token(anonymous_routes(), Method::GET, uri).await;
+
+            assert_eq!(StatusCode::OK, actual_status, "{uri} must be anonymous");
+        }
+
+        let actual_status = call_without_token(anonymous_routes(), Method::GET, "/healthz").await;
+
+        assert_eq!(StatusCode::UNAUTHORIZED, actual_status);
+    }
+
+    #[actix_web::test]
+    async fn will_return_200_on_anonymous_pattern() {
+        let anonymous_routes = || vec![AnonymousRoute::path("/api/games/{game_id}/score")];
+
+        let actual_status =
+            call_without_token(anonymous_routes(), Method::GET, "/api/games/123/score").await;
+
+        assert_eq!(StatusCode::OK, actual_status);
+
+        let actual_status =
+            call_without_token(anonymous_routes(), Method::GET, "/api/games/123").await;
+
+        assert_eq!(StatusCode::UNAUTHORIZED, actual_status);
+    }
+
+    #[actix_web::test]
+    async fn will_return_401_on_anonymous_method_mismatch() {
+        let anonymous_routes = || vec![AnonymousRoute::path("/test").with_method(Method::GET)];
+
+        let actual_status = call_without_token(anonymous_routes(), Method::GET, "/test").await;
+
+        assert_eq!(StatusCode::OK, actual_status);
+
+        let actual_status = call_without_token(anonymous_routes(), Method::DELETE, "/test").await;
+
+        assert_eq!(StatusCode::UNAUTHORIZED, actual_status);
+    }
+
+    #[actix_web::test]
+    async fn will_accept_case_insensitive_scheme_with_extra_whitespace() {
+        let app_state = Arc::new(create_test_app_state());
+
+        let valid_token = app_state
+            .token_service
+            .generate_token("test_subject", "test player", &[])
+            .await
+            .unwrap();
+
+        let uut_app = test::init_service(
+            App::new()
+                .wrap(JwtAuthentication::new(vec![]))
+                .app_data(web::Data::new(app_state.clone()))
+                .route("/", web::get().to(HttpResponse::Ok)),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/")
+            .insert_header((
+                http::header::AUTHORIZATION,
+                format!("  bEaReR   {}  ", &valid_token.access_token),
+            ))
+            .to_request();
+
+        let actual_resp = test::call_service(&uut_app, req).await;
+
+        assert_eq!(StatusCode::OK, actual_resp.status());
+    }
+
+    #[actix_web::test]
+    async fn will_return_challenge_on_rejected_token() {
+        let app_state = Arc::new(create_test_app_state());
+
+        let uut_app = test::init_service(
+            App::new()
+                .wrap(JwtAuthentication::new(vec![]))
+                .app_data(web::Data::new(app_state.clone()))
+                .route("/", web::get().to(HttpResponse::Ok)),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/")
+            .insert_header((http::header::AUTHORIZATION, "Bearer bad_token"))
+            .to_request();
+
+        let actual_resp = test::call_service(&uut_app, req).await;
+
+        assert_eq!(StatusCode::UNAUTHORIZED, actual_resp.status());
+        assert_eq!(
+            "Bearer error=\"invalid_token\", error_description=\"bearer token is malformed!\"",
+            actual_resp
+                .headers()
+                .get(http::header::WWW_AUTHENTICATE)
+                .unwrap()
+        );
+    }
+
+    /// Token service that yields before validating, like one hitting JWKS or the database
+    struct DelayedTokenService(JwtTokenService);
+
+    impl TokenService for DelayedTokenService {
+        fn generate_token<'a>(
+            &'a self,
+            subject: &'a str,
+            name: &'a str,
+            roles: &'a [String],
+        ) -> BoxFuture<'a, Result<JwtToken, Box<dyn std::error::Error + Send + Sync>>> {
+            self.0.generate_token(subject, name, roles)
+        }
+
+        fn get_validated_claims<'a>(
+            &'a self,
+            token: &'a str,
+        ) -> BoxFuture<'a, Result<UserClaims, TokenError>> {
+            async move {
+                actix_web::rt::time::sleep(std::time::Duration::from_millis(1)).await;
+                self.0.get_validated_claims(token).await
+            }
+            .boxed()
+        }
+
+        fn get_public_jwks(&self) -> JwkSet {
+            self.0.get_public_jwks()
+        }
+    }
+
+    #[actix_web::test]
+    async fn will_await_async_token_validation() {
+        let app_state = Arc::new(AppState {
+            token_service: Box::new(DelayedTokenService(create_test_token_service())),
+            ..create_test_app_state()
+        });
+
+        let valid_token = app_state
+            .token_service
+            .generate_token("test_subject", "test player", &[])
+            .await
+            .unwrap();
+
+        let uut_app = test::init_service(
+            App::new()
+                .wrap(JwtAuthentication::new(vec![]))
+                .app_data(web::Data::new(app_state.clone()))
+                .route("/", web::get().to(HttpResponse::Ok)),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/")
+            .insert_header((
+                http::header::AUTHORIZATION,
+                format!("Bearer {}", &valid_token.access_token),
+            ))
+            .to_request();
+
+        let actual_resp = test::call_service(&uut_app, req).await;
+
+        assert_eq!(StatusCode::OK, actual_resp.status());
+    }
 }
@@ -1,3 +1,25 @@
+pub mod api_key;
+
+pub mod apple_identity_provider;
+
+pub mod authenticated_player;
+
+pub mod guest_identity_provider;
+
+pub mod identity_provider;
+
 pub mod jwt_auth_middleware;
 
+pub mod oidc_client;
+
+pub mod refresh_token;
+
+pub mod role_authorization;
+
+pub mod signing_key;
+
+pub mod token_error;
+
+pub mod token_revocation;
+
 pub mod token_service;
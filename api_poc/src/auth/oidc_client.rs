@@ -0,0 +1,396 @@
+use std::{
+    sync::{Mutex, RwLock},
+    time::{Duration, Instant},
+};
+
+use futures_util::{future::BoxFuture, FutureExt as _};
+use jsonwebtoken::{decode, decode_header, jwk::JwkSet, Algorithm, DecodingKey, Validation};
+use log::info;
+use serde::Deserialize;
+
+use crate::{
+    api_error::{ApiError, ApiResult},
+    app_config::OidcProviderConfig,
+    auth::identity_provider::IdentityProvider,
+};
+
+/// Token endpoint response. Only the ID token is needed to establish player identity
+#[derive(Deserialize)]
+struct TokenEndpointResponse {
+    id_token: String,
+}
+
+/// Validated ID token claims
+#[derive(Debug, Clone, Deserialize)]
+pub struct OidcIdentity {
+    /// Identity unique id in the context of the provider
+    pub sub: String,
+    pub name: Option<String>,
+    pub email: Option<String>,
+}
+
+impl OidcIdentity {
+    /// Player display name. Falls back to email, and then to a generic name
+    pub fn get_display_name(&self) -> &str {
+        self.name
+            .as_deref()
+            .or(self.email.as_deref())
+            .unwrap_or("player")
+    }
+}
+
+/// OpenID Connect authorization code flow client.
+/// Used as is for Google and generic OIDC providers.
+/// See [Google OpenID Connect](https://developers.google.com/identity/openid-connect/openid-connect#server-flow)
+/// Tokens signed with unknown keys trigger a jwks fetch, so fetches are throttled to keep
+/// forged tokens from hammering the provider
+const JWKS_MIN_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+pub struct OidcClient {
+    /// Provider name stored with player identity
+    provider_name: String,
+    config: OidcProviderConfig,
+    http_client: reqwest::Client,
+    /// Provider signing keys. Keys are re-fetched when the token is signed with an unknown key
+    jwks: RwLock<Option<JwkSet>>,
+    /// Time of the last jwks fetch attempt
+    jwks_refreshed_at: Mutex<Option<Instant>>,
+}
+
+impl OidcClient {
+    pub fn new(provider_name: &str, config: OidcProviderConfig) -> Self {
+        Self {
+            provider_name: provider_name.into(),
+            config,
+            http_client: reqwest::Client::new(),
+            jwks: RwLock::new(None),
+            jwks_refreshed_at: Mutex::new(None),
+        }
+    }
+
+    /// Exchange authorization code for an ID token, and validate ID token claims
+    pub async fn get_identity_from_code(
+        &self,
+        authorization_code: &str,
+    ) -> ApiResult<OidcIdentity> {
+        self.get_identity_from_code_with_secret(authorization_code, &self.config.client_secret)
+            .await
+    }
+
+    /// Same as `get_identity_from_code`, for providers that require per-request client secret
+    pub async fn get_identity_from_code_with_secret(
+        &self,
+        authorization_code: &str,
+        client_secret: &str,
+    ) -> ApiResult<OidcIdentity> {
+        let id_token = self
+            .exchange_code(authorization_code, client_secret)
+            .await?;
+
+        self.get_validated_identity(&id_token).await
+    }
+
+    async fn exchange_code(
+        &self,
+        authorization_code: &str,
+        client_secret: &str,
+    ) -> ApiResult<String> {
+        let token_params = [
+            ("grant_type", "authorization_code"),
+            ("code", authorization_code),
+            ("client_id", &self.config.client_id),
+            ("client_secret", client_secret),
+            ("redirect_uri", &self.config.redirect_uri),
+        ];
+
+        let response = self
+            .http_client
+            .post(&self.config.token_url)
+            .form(&token_params)
+            .send()
+            .await
+            .map_err(|err| ApiError::Internal(format!("failed to call token endpoint: {err}")))?;
+
+        // provider rejects invalid, expired, or already used codes with a client error
+        if response.status().is_client_error() {
+            return Err(ApiError::Unauthorized(
+                "authorization code is invalid!".into(),
+            ));
+        }
+
+        let token_response: TokenEndpointResponse = response
+            .error_for_status()
+            .map_err(|err| ApiError::Internal(format!("token endpoint failed: {err}")))?
+            .json()
+            .await
+            .map_err(|err| {
+                ApiError::Internal(format!("failed to parse token endpoint response: {err}"))
+            })?;
+
+        Ok(token_response.id_token)
+    }
+
+    async fn get_validated_identity(&self, id_token: &str) -> ApiResult<OidcIdentity> {
+        let header = decode_header(id_token)
+            .map_err(|err| ApiError::Unauthorized(format!("id token is malformed: {err}")))?;
+
+        let kid = header
+            .kid
+            .ok_or_else(|| ApiError::Unauthorized("id token has no key id!".into()))?;
+
+        let decoding_key = match self.get_cached_decoding_key(&kid)? {
+            Some(decoding_key) => decoding_key,
+            None => {
+                // provider may have rotated its keys since the last fetch
+                if self.try_start_jwks_refresh(Instant::now())? {
+                    self.refresh_jwks().await?;
+                }
+
+                self.get_cached_decoding_key(&kid)?.ok_or_else(|| {
+                    ApiError::Unauthorized(format!("id token signing key {kid} is unknown!"))
+                })?
+            }
+        };
+
+        let mut validation = Validation::new(Algorithm::RS256);
+        validation.set_audience(&[&self.config.client_id]);
+        validation.set_issuer(&[&self.config.issuer]);
+        validation.set_required_spec_claims(&["exp", "aud", "iss", "sub"]);
+
+        let token_data = decode::<OidcIdentity>(id_token, &decoding_key, &validation)
+            .map_err(|err| ApiError::Unauthorized(format!("id token is invalid: {err}")))?;
+
+        Ok(token_data.claims)
+    }
+
+    fn get_cached_decoding_key(&self, kid: &str) -> ApiResult<Option<DecodingKey>> {
+        let jwks = self
+            .jwks
+            .read()
+            .map_err(|_| ApiError::Internal("jwks cache lock is poisoned!".into()))?;
+
+        let Some(jwk) = jwks.as_ref().and_then(|jwks| jwks.find(kid)) else {
+            return Ok(None);
+        };
+
+        let decoding_key = DecodingKey::from_jwk(jwk)
+            .map_err(|err| ApiError::Internal(format!("provider jwk {kid} is invalid: {err}")))?;
+
+        Ok(Some(decoding_key))
+    }
+
+    /// Record jwks fetch attempt. Returns false if keys were fetched less than the min interval ago
+    fn try_start_jwks_refresh(&self, now: Instant) -> ApiResult<bool> {
+        let mut jwks_refreshed_at = self
+            .jwks_refreshed_at
+            .lock()
+            .map_err(|_| ApiError::Internal("jwks refresh lock is poisoned!".into()))?;
+
+        let is_throttled = jwks_refreshed_at.is_some_and(|refreshed_at| {
+            now.duration_since(refreshed_at) < JWKS_MIN_REFRESH_INTERVAL
+        });
+
+        if !is_throttled {
+            *jwks_refreshed_at = Some(now);
+        }
+
+        Ok(!is_throttled)
+    }
+
+    async fn refresh_jwks(&self) -> ApiResult<()> {
+        info!(
+            "fetching provider signing keys from {}...",
+            self.config.jwks_url
+        );
+
+        let jwks: JwkSet = self
+            .http_client
+            .get(&self.config.jwks_url)
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .map_err(|err| ApiError::Internal(format!("failed to fetch provider jwks: {err}")))?
+            .json()
+            .await
+            .map_err(|err| ApiError::Internal(format!("failed to parse provider jwks: {err}")))?;
+
+        *self
+            .jwks
+            .write()
+            .map_err(|_| ApiError::Internal("jwks cache lock is poisoned!".into()))? = Some(jwks);
+
+        Ok(())
+    }
+}
+
+impl IdentityProvider for OidcClient {
+    fn get_provider_name(&self) -> &str {
+        &self.provider_name
+    }
+
+    fn get_identity<'a>(&'a self, credential: &'a str) -> BoxFuture<'a, ApiResult<OidcIdentity>> {
+        self.get_identity_from_code(credential).boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{rt, web, App, HttpResponse, HttpServer};
+    use chrono::Utc;
+    use jsonwebtoken::{encode, EncodingKey, Header};
+    use serde_json::json;
+
+    use super::*;
+
+    const TEST_CLIENT_ID: &str = "test_client_id";
+    const TEST_ISSUER: &str = "https://stub.oidc.local";
+    const TEST_VALID_CODE: &str = "valid_code";
+
+    fn create_test_id_token(kid: &str, aud: &str) -> String {
+        let now = Utc::now().timestamp();
+
+        let claims = json!({
+            "iss": TEST_ISSUER,
+            "aud": aud,
+            "sub": "test_google_sub",
+            "email": "player@example.com",
+            "iat": now,
+            "exp": now + 300,
+        });
+
+        let mut header = Header::new(Algorithm::RS256);
+        header.kid = Some(kid.into());
+
+        encode(
+            &header,
+            &claims,
+            &EncodingKey::from_rsa_der(include_bytes!("../../tests/fixtures/oidc_test_key.der")),
+        )
+        .expect("test id token must be signed")
+    }
+
+    /// Start stub OIDC provider that accepts a single authorization code
+    /// and issues the supplied ID token for it
+    fn start_stub_provider(id_token: String) -> OidcProviderConfig {
+        let server = HttpServer::new(move || {
+            let id_token = id_token.clone();
+
+            App::new()
+                .route(
+                    "/token",
+                    web::post().to(move |form: web::Form<Vec<(String, String)>>| {
+                        let id_token = id_token.clone();
+                        async move {
+                            let is_valid_code = form
+                                .iter()
+                                .any(|(key, value)| key == "code" && value == TEST_VALID_CODE);
+
+                            if is_valid_code {
+                                HttpResponse::Ok().json(json!({ "id_token": id_token }))
+                            } else {
+                                HttpResponse::BadRequest().json(json!({ "error": "invalid_grant" }))
+                            }
+                        }
+                    }),
+                )
+                .route(
+                    "/jwks",
+                    web::get().to(|| async {
+                        HttpResponse::Ok()
+                            .content_type("application/json")
+                            .body(include_str!("../../tests/fixtures/oidc_test_jwks.json"))
+                    }),
+                )
+        })
+        .workers(1)
+        .bind(("127.0.0.1", 0))
+        .expect("stub provider must bind to a free port");
+
+        let stub_url = format!("http://{}", server.addrs()[0]);
+
+        rt::spawn(server.run());
+
+        OidcProviderConfig {
+            client_id: TEST_CLIENT_ID.into(),
+            client_secret: "test_client_secret".into(),
+            redirect_uri: "postmessage".into(),
+            issuer: TEST_ISSUER.into(),
+            token_url: format!("{stub_url}/token"),
+            jwks_url: format!("{stub_url}/jwks"),
+        }
+    }
+
+    #[actix_web::test]
+    async fn will_return_identity_from_valid_code() {
+        let config = start_stub_provider(create_test_id_token("test-key", TEST_CLIENT_ID));
+        let uut_client = OidcClient::new("test", config);
+
+        let actual_identity = uut_client
+            .get_identity_from_code(TEST_VALID_CODE)
+            .await
+            .unwrap();
+
+        assert_eq!("test_google_sub", actual_identity.sub);
+        assert_eq!("player@example.com", actual_identity.get_display_name());
+    }
+
+    #[actix_web::test]
+    async fn will_return_unauthorized_on_invalid_code() {
+        let config = start_stub_provider(create_test_id_token("test-key", TEST_CLIENT_ID));
+        let uut_client = OidcClient::new("test", config);
+
+        let actual_err = uut_client
+            .get_identity_from_code("invalid_code")
+            .await
+            .unwrap_err();
+
+        assert!(matches!(actual_err, ApiError::Unauthorized(_)));
+    }
+
+    #[actix_web::test]
+    async fn will_return_unauthorized_on_invalid_audience() {
+        let config = start_stub_provider(create_test_id_token("test-key", "other_client_id"));
+        let uut_client = OidcClient::new("test", config);
+
+        let actual_err = uut_client
+            .get_identity_from_code(TEST_VALID_CODE)
+            .await
+            .unwrap_err();
+
+        assert!(matches!(actual_err, ApiError::Unauthorized(_)));
+    }
+
+    #[actix_web::test]
+    async fn will_return_unauthorized_on_unknown_signing_key() {
+        let config = start_stub_provider(create_test_id_token("unknown-key", TEST_CLIENT_ID));
+        let uut_client = OidcClient::new("test", config);
+
+        let actual_err = uut_client
+            .get_identity_from_code(TEST_VALID_CODE)
+            .await
+            .unwrap_err();
+
+        assert_eq!(
+            "id token signing key unknown-key is unknown!",
+            actual_err.to_string()
+        );
+    }
+
+    #[test]
+    fn will_throttle_jwks_refresh_within_min_interval() {
+        let uut_client = OidcClient::new("test", OidcProviderConfig::default());
+        let now = Instant::now();
+
+        let actual_refreshes = [
+            uut_client.try_start_jwks_refresh(now).unwrap(),
+            uut_client
+                .try_start_jwks_refresh(now + Duration::from_secs(59))
+                .unwrap(),
+            uut_client
+                .try_start_jwks_refresh(now + JWKS_MIN_REFRESH_INTERVAL)
+                .unwrap(),
+        ];
+
+        assert_eq!([true, false, true], actual_refreshes);
+    }
+}
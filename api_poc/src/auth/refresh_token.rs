@@ -0,0 +1,108 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use bson::oid::ObjectId;
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+
+use crate::api_error::{ApiError, ApiResult};
+
+/// Number of random bytes in the refresh token secret
+const REFRESH_TOKEN_SECRET_LEN: usize = 32;
+
+/// Opaque refresh token in `{player id}.{random secret}` format.
+/// Player id allows token lookup without an index, while only the token hash is persisted.
+pub struct RefreshToken {
+    pub player_id: ObjectId,
+    token: String,
+}
+
+impl RefreshToken {
+    /// Generate new random refresh token for the player
+    pub fn new(player_id: ObjectId) -> Self {
+        let mut secret = [0u8; REFRESH_TOKEN_SECRET_LEN];
+        rand::thread_rng().fill_bytes(&mut secret);
+
+        Self {
+            player_id,
+            token: format!("{}.{}", player_id.to_hex(), URL_SAFE_NO_PAD.encode(secret)),
+        }
+    }
+
+    /// Parse refresh token presented by the client
+    pub fn parse(token: &str) -> ApiResult<Self> {
+        let invalid_token = || ApiError::Unauthorized("refresh token is invalid!".into());
+
+        let (player_id, secret) = token.split_once('.').ok_or_else(invalid_token)?;
+
+        let player_id = ObjectId::parse_str(player_id).map_err(|_| invalid_token())?;
+
+        let is_valid_secret = URL_SAFE_NO_PAD
+            .decode(secret)
+            .is_ok_and(|secret| secret.len() == REFRESH_TOKEN_SECRET_LEN);
+
+        if !is_valid_secret {
+            return Err(invalid_token());
+        }
+
+        Ok(Self {
+            player_id,
+            token: token.into(),
+        })
+    }
+
+    /// SHA-256 hash of the token. Refresh tokens are never persisted as is
+    pub fn get_hash(&self) -> String {
+        format!("{:x}", Sha256::digest(self.token.as_bytes()))
+    }
+
+    /// Token value returned to the client
+    pub fn as_str(&self) -> &str {
+        &self.token
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn will_parse_generated_token() {
+        let player_id = ObjectId::new();
+        let uut_token = RefreshToken::new(player_id);
+
+        let actual_token = RefreshToken::parse(uut_token.as_str()).unwrap();
+
+        assert_eq!(player_id, actual_token.player_id);
+        assert_eq!(uut_token.get_hash(), actual_token.get_hash());
+        assert_ne!(uut_token.as_str(), uut_token.get_hash());
+    }
+
+    #[test]
+    fn will_generate_unique_tokens() {
+        let player_id = ObjectId::new();
+
+        let first_token = RefreshToken::new(player_id);
+        let second_token = RefreshToken::new(player_id);
+
+        assert_ne!(first_token.as_str(), second_token.as_str());
+        assert_ne!(first_token.get_hash(), second_token.get_hash());
+    }
+
+    #[test]
+    fn will_return_error_on_malformed_token() {
+        let player_id = ObjectId::new().to_hex();
+
+        let malformed_tokens = [
+            String::from("no_separator"),
+            format!("not_an_object_id.{}", URL_SAFE_NO_PAD.encode([0u8; 32])),
+            format!("{player_id}.too_short"),
+            format!("{player_id}.{}", URL_SAFE_NO_PAD.encode([0u8; 16])),
+        ];
+
+        for malformed_token in malformed_tokens {
+            assert!(
+                RefreshToken::parse(&malformed_token).is_err(),
+                "{malformed_token} must be rejected"
+            );
+        }
+    }
+}
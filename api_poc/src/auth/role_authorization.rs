@@ -0,0 +1,163 @@
+use std::future::{ready, Ready};
+
+use actix_web::{
+    body::EitherBody,
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    Error, HttpMessage, ResponseError,
+};
+use futures_util::{future::LocalBoxFuture, FutureExt as _, TryFutureExt as _};
+use log::warn;
+
+use crate::auth::{token_error::TokenError, token_service::UserClaims};
+
+/// Role required for admin endpoints
+pub const ADMIN_ROLE: &str = "admin";
+/// Role required for moderation endpoints
+pub const MODERATOR_ROLE: &str = "moderator";
+
+/// Role of guest players. Assigned on sign in, so it can't be granted
+pub const GUEST_ROLE: &str = "guest";
+
+/// Roles that can be granted to players
+pub const KNOWN_ROLES: [&str; 2] = [ADMIN_ROLE, MODERATOR_ROLE];
+
+/// Role guard for a scope or a single resource.
+/// Must be used under `JwtAuthentication` since it relies on validated token claims.
+/// ex:
+/// ```ignore
+/// web::scope("/admin").wrap(RequireRole::new(ADMIN_ROLE))
+/// ```
+pub struct RequireRole {
+    role: String,
+}
+
+impl RequireRole {
+    pub fn new(role: &str) -> Self {
+        Self { role: role.into() }
+    }
+}
+
+/// Role guard middleware
+pub struct RequireRoleMiddleware<S> {
+    service: S,
+    role: String,
+}
+
+impl<S, B> Service<ServiceRequest> for RequireRoleMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let token_error = match req.extensions().get::<UserClaims>() {
+            Some(claims) if claims.roles.contains(&self.role) => None,
+            // token is valid, but it doesn't grant access to this resource
+            Some(claims) => {
+                warn!("{} is missing {} role", claims.sub, self.role);
+                Some(TokenError::InsufficientRole(self.role.clone()))
+            }
+            None => Some(TokenError::Missing),
+        };
+
+        match token_error {
+            None => self
+                .service
+                .call(req)
+                .map_ok(ServiceResponse::map_into_left_body)
+                .boxed_local(),
+            Some(token_error) => Box::pin(async move {
+                Ok(req.into_response(token_error.error_response().map_into_right_body()))
+            }),
+        }
+    }
+}
+
+/// Role guard middleware factory
+impl<S, B> Transform<S, ServiceRequest> for RequireRole
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = RequireRoleMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RequireRoleMiddleware {
+            service,
+            role: self.role.clone(),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use actix_web::{
+        http::{self, StatusCode},
+        test, web, App, HttpResponse,
+    };
+
+    use crate::{
+        auth::jwt_auth_middleware::JwtAuthentication, test_helpers::create_test_app_state,
+    };
+
+    use super::*;
+
+    async fn call_admin_scope(roles: &[String]) -> StatusCode {
+        let app_state = Arc::new(create_test_app_state());
+
+        let valid_token = app_state
+            .token_service
+            .generate_token("test_subject", "test player", roles)
+            .await
+            .unwrap();
+
+        let uut_app = test::init_service(
+            App::new()
+                .wrap(JwtAuthentication::new(vec![]))
+                .app_data(web::Data::new(app_state.clone()))
+                .service(
+                    web::scope("/admin")
+                        .wrap(RequireRole::new(ADMIN_ROLE))
+                        .route("/test", web::get().to(HttpResponse::Ok)),
+                ),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/admin/test")
+            .insert_header((
+                http::header::AUTHORIZATION,
+                format!("Bearer {}", &valid_token.access_token),
+            ))
+            .to_request();
+
+        test::call_service(&uut_app, req).await.status()
+    }
+
+    #[actix_web::test]
+    async fn will_return_200_on_required_role() {
+        let actual_status = call_admin_scope(&[ADMIN_ROLE.into()]).await;
+
+        assert_eq!(StatusCode::OK, actual_status);
+    }
+
+    #[actix_web::test]
+    async fn will_return_403_on_missing_role() {
+        let actual_status = call_admin_scope(&[MODERATOR_ROLE.into()]).await;
+
+        assert_eq!(StatusCode::FORBIDDEN, actual_status);
+    }
+}
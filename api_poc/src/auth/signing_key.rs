@@ -0,0 +1,389 @@
+use std::{error::Error, fs};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use chrono::{DateTime, Utc};
+use jsonwebtoken::{
+    jwk::{
+        AlgorithmParameters, CommonParameters, EllipticCurve, EllipticCurveKeyParameters,
+        EllipticCurveKeyType, Jwk, KeyAlgorithm, PublicKeyUse, RSAKeyParameters, RSAKeyType,
+    },
+    Algorithm, DecodingKey, EncodingKey,
+};
+use p256::{elliptic_curve::sec1::ToEncodedPoint, pkcs8::DecodePrivateKey as _};
+use rsa::{pkcs1::DecodeRsaPrivateKey, traits::PublicKeyParts, RsaPrivateKey};
+use sha2::{Digest, Sha256};
+
+use crate::app_config::AppConfig;
+
+/// Key used to sign and verify api tokens.
+/// Asymmetric keys also publish their public part so other services can verify api tokens.
+pub struct JwtSigningKey {
+    pub algorithm: Algorithm,
+    /// Key id stamped into token header. Derived from the key, so the same key always gets the same id
+    pub kid: String,
+    encoding_key: EncodingKey,
+    decoding_key: DecodingKey,
+    /// Public key in JWK format. Symmetric keys are never published
+    public_jwk: Option<Jwk>,
+}
+
+impl JwtSigningKey {
+    /// HS256 key using a shared secret
+    pub fn from_secret(secret: &str) -> Self {
+        Self {
+            algorithm: Algorithm::HS256,
+            kid: derive_kid(&[b"HS256", secret.as_bytes()]),
+            encoding_key: EncodingKey::from_secret(secret.as_ref()),
+            decoding_key: DecodingKey::from_secret(secret.as_ref()),
+            public_jwk: None,
+        }
+    }
+
+    /// RS256 key from PKCS#8 or PKCS#1 private key PEM
+    pub fn from_rsa_pem(private_key_pem: &str) -> Result<Self, Box<dyn Error>> {
+        let private_key = RsaPrivateKey::from_pkcs8_pem(private_key_pem)
+            .or_else(|_| RsaPrivateKey::from_pkcs1_pem(private_key_pem))?;
+
+        let key_params = AlgorithmParameters::RSA(RSAKeyParameters {
+            key_type: RSAKeyType::RSA,
+            n: URL_SAFE_NO_PAD.encode(private_key.n().to_bytes_be()),
+            e: URL_SAFE_NO_PAD.encode(private_key.e().to_bytes_be()),
+        });
+
+        Self::from_asymmetric_key(
+            Algorithm::RS256,
+            KeyAlgorithm::RS256,
+            EncodingKey::from_rsa_pem(private_key_pem.as_bytes())?,
+            key_params,
+        )
+    }
+
+    /// ES256 key from PKCS#8 P-256 private key PEM
+    pub fn from_ec_pem(private_key_pem: &str) -> Result<Self, Box<dyn Error>> {
+        let private_key = p256::SecretKey::from_pkcs8_pem(private_key_pem)?;
+        let public_point = private_key.public_key().to_encoded_point(false);
+
+        let (Some(x), Some(y)) = (public_point.x(), public_point.y()) else {
+            return Err("EC public key must be uncompressed!".into());
+        };
+
+        let key_params = AlgorithmParameters::EllipticCurve(EllipticCurveKeyParameters {
+            key_type: EllipticCurveKeyType::EC,
+            curve: EllipticCurve::P256,
+            x: URL_SAFE_NO_PAD.encode(x),
+            y: URL_SAFE_NO_PAD.encode(y),
+        });
+
+        Self::from_asymmetric_key(
+            Algorithm::ES256,
+            KeyAlgorithm::ES256,
+            EncodingKey::from_ec_pem(private_key_pem.as_bytes())?,
+            key_params,
+        )
+    }
+
+    /// Build signing key for the algorithm.
+    /// Asymmetric algorithms require a private key PEM file, while HS256 uses the shared secret.
+    pub fn from_settings(
+        algorithm: Algorithm,
+        secret: &str,
+        private_key_file: Option<&str>,
+    ) -> Result<Self, Box<dyn Error>> {
+        let read_private_key = || -> Result<String, Box<dyn Error>> {
+            let key_file = private_key_file
+                .ok_or("jwt private key file is required for asymmetric algorithms!")?;
+
+            fs::read_to_string(key_file)
+                .map_err(|err| format!("failed to read jwt private key '{key_file}': {err}").into())
+        };
+
+        match algorithm {
+            Algorithm::HS256 => Ok(Self::from_secret(secret)),
+            Algorithm::RS256 => Self::from_rsa_pem(&read_private_key()?),
+            Algorithm::ES256 => Self::from_ec_pem(&read_private_key()?),
+            unsupported => Err(format!("jwt algorithm {unsupported:?} is not supported!").into()),
+        }
+    }
+
+    pub fn get_encoding_key(&self) -> &EncodingKey {
+        &self.encoding_key
+    }
+
+    pub fn get_decoding_key(&self) -> &DecodingKey {
+        &self.decoding_key
+    }
+
+    pub fn get_public_jwk(&self) -> Option<&Jwk> {
+        self.public_jwk.as_ref()
+    }
+
+    fn from_asymmetric_key(
+        algorithm: Algorithm,
+        key_algorithm: KeyAlgorithm,
+        encoding_key: EncodingKey,
+        key_params: AlgorithmParameters,
+    ) -> Result<Self, Box<dyn Error>> {
+        let kid = derive_kid(&[serde_json::to_string(&key_params)?.as_bytes()]);
+
+        let public_jwk = Jwk {
+            common: CommonParameters {
+                key_id: Some(kid.clone()),
+                public_key_use: Some(PublicKeyUse::Signature),
+                key_algorithm: Some(key_algorithm),
+                ..CommonParameters::default()
+            },
+            algorithm: key_params,
+        };
+
+        // verification uses the same public key that is published
+        let decoding_key = DecodingKey::from_jwk(&public_jwk)?;
+
+        Ok(Self {
+            algorithm,
+            kid,
+            encoding_key,
+            decoding_key,
+            public_jwk: Some(public_jwk),
+        })
+    }
+}
+
+fn derive_kid(key_material: &[&[u8]]) -> String {
+    let key_hash = key_material
+        .iter()
+        .fold(Sha256::new(), |hasher, material| {
+            hasher.chain_update(material)
+        })
+        .finalize();
+
+    // shortened hash is enough to tell keys apart without revealing anything useful about the key
+    URL_SAFE_NO_PAD.encode(&key_hash[..12])
+}
+
+/// Signing key that is no longer used for new tokens
+struct RetiredSigningKey {
+    key: JwtSigningKey,
+    accepted_until: DateTime<Utc>,
+}
+
+/// Api token keys.
+/// New tokens are always signed with the current key,
+/// while retired keys are still accepted for verification until their grace period expires.
+pub struct JwtKeySet {
+    current_key: JwtSigningKey,
+    retired_keys: Vec<RetiredSigningKey>,
+}
+
+impl JwtKeySet {
+    pub fn new(current_key: JwtSigningKey) -> Self {
+        Self {
+            current_key,
+            retired_keys: Vec::new(),
+        }
+    }
+
+    pub fn with_retired_key(mut self, key: JwtSigningKey, accepted_until: DateTime<Utc>) -> Self {
+        self.retired_keys.push(RetiredSigningKey {
+            key,
+            accepted_until,
+        });
+        self
+    }
+
+    /// Build key set from configured current and previous keys.
+    /// Previous key is accepted until the configured time, which should leave enough time
+    /// for tokens signed before the rotation to expire.
+    pub fn from_config(config: &AppConfig) -> Result<Self, Box<dyn Error>> {
+        let current_key = JwtSigningKey::from_settings(
+            config.jwt_algorithm,
+            &config.jwt_signing_key,
+            config.jwt_private_key_file.as_deref(),
+        )?;
+
+        let key_set = Self::new(current_key);
+
+        if config.jwt_previous_signing_key.is_none()
+            && config.jwt_previous_private_key_file.is_none()
+        {
+            return Ok(key_set);
+        }
+
+        let previous_key = JwtSigningKey::from_settings(
+            config
+                .jwt_previous_algorithm
+                .unwrap_or(config.jwt_algorithm),
+            config
+                .jwt_previous_signing_key
+                .as_deref()
+                .unwrap_or_default(),
+            config.jwt_previous_private_key_file.as_deref(),
+        )?;
+
+        let accepted_until = config
+            .jwt_previous_key_accepted_until
+            .as_deref()
+            .ok_or("jwt_previous_key_accepted_until must be set together with the previous key!")?;
+        let accepted_until = DateTime::parse_from_rfc3339(accepted_until)
+            .map_err(|err| format!("jwt_previous_key_accepted_until is invalid: {err}"))?
+            .with_timezone(&Utc);
+
+        Ok(key_set.with_retired_key(previous_key, accepted_until))
+    }
+
+    /// Key used for signing new tokens
+    pub fn get_signing_key(&self) -> &JwtSigningKey {
+        &self.current_key
+    }
+
+    /// Find token verification key by the id from token header.
+    /// Retired keys are returned only within their grace period.
+    pub fn get_verification_key(&self, kid: &str) -> Option<&JwtSigningKey> {
+        if self.current_key.kid == kid {
+            return Some(&self.current_key);
+        }
+
+        self.get_accepted_retired_keys().find(|key| key.kid == kid)
+    }
+
+    /// Public keys of the current and accepted retired keys
+    pub fn get_public_jwks(&self) -> Vec<Jwk> {
+        std::iter::once(&self.current_key)
+            .chain(self.get_accepted_retired_keys())
+            .filter_map(JwtSigningKey::get_public_jwk)
+            .cloned()
+            .collect()
+    }
+
+    fn get_accepted_retired_keys(&self) -> impl Iterator<Item = &JwtSigningKey> {
+        let now = Utc::now();
+
+        self.retired_keys
+            .iter()
+            .filter(move |retired_key| now < retired_key.accepted_until)
+            .map(|retired_key| &retired_key.key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_RSA_KEY_PEM: &str = include_str!("../../tests/fixtures/jwt_test_rsa_key.pem");
+    const TEST_EC_KEY_PEM: &str = include_str!("../../tests/fixtures/jwt_test_ec_key.pem");
+
+    #[test]
+    fn will_not_publish_secret_key() {
+        let uut_key = JwtSigningKey::from_secret("secret key");
+
+        assert_eq!(Algorithm::HS256, uut_key.algorithm);
+        assert!(uut_key.get_public_jwk().is_none());
+    }
+
+    #[test]
+    fn will_publish_rsa_public_key() {
+        let uut_key = JwtSigningKey::from_rsa_pem(TEST_RSA_KEY_PEM).unwrap();
+
+        assert_eq!(Algorithm::RS256, uut_key.algorithm);
+
+        let actual_jwk = uut_key.get_public_jwk().expect("RSA key must be published");
+
+        assert!(matches!(actual_jwk.algorithm, AlgorithmParameters::RSA(_)));
+        assert_eq!(Some(KeyAlgorithm::RS256), actual_jwk.common.key_algorithm);
+    }
+
+    #[test]
+    fn will_publish_ec_public_key() {
+        let uut_key = JwtSigningKey::from_ec_pem(TEST_EC_KEY_PEM).unwrap();
+
+        assert_eq!(Algorithm::ES256, uut_key.algorithm);
+
+        let actual_jwk = uut_key.get_public_jwk().expect("EC key must be published");
+
+        assert!(matches!(
+            actual_jwk.algorithm,
+            AlgorithmParameters::EllipticCurve(_)
+        ));
+    }
+
+    #[test]
+    fn will_return_error_on_invalid_pem() {
+        assert!(JwtSigningKey::from_rsa_pem("not a pem").is_err());
+        assert!(JwtSigningKey::from_ec_pem(TEST_RSA_KEY_PEM).is_err());
+    }
+
+    #[test]
+    fn will_require_private_key_file_for_asymmetric_algorithm() {
+        let actual_result = JwtSigningKey::from_settings(Algorithm::RS256, "", None);
+
+        assert!(actual_result.is_err());
+    }
+
+    #[test]
+    fn will_derive_stable_kid() {
+        let first_key = JwtSigningKey::from_secret("secret key");
+        let same_key = JwtSigningKey::from_secret("secret key");
+        let other_key = JwtSigningKey::from_secret("other secret key");
+
+        assert_eq!(first_key.kid, same_key.kid);
+        assert_ne!(first_key.kid, other_key.kid);
+
+        let rsa_key = JwtSigningKey::from_rsa_pem(TEST_RSA_KEY_PEM).unwrap();
+
+        assert_eq!(
+            Some(&rsa_key.kid),
+            rsa_key.get_public_jwk().unwrap().common.key_id.as_ref()
+        );
+    }
+
+    #[test]
+    fn will_accept_retired_key_within_grace_period() {
+        let retired_kid = JwtSigningKey::from_rsa_pem(TEST_RSA_KEY_PEM).unwrap().kid;
+        let expired_kid = JwtSigningKey::from_secret("expired key").kid;
+
+        let uut_key_set = JwtKeySet::new(JwtSigningKey::from_ec_pem(TEST_EC_KEY_PEM).unwrap())
+            .with_retired_key(
+                JwtSigningKey::from_rsa_pem(TEST_RSA_KEY_PEM).unwrap(),
+                Utc::now() + chrono::Duration::minutes(5),
+            )
+            .with_retired_key(
+                JwtSigningKey::from_secret("expired key"),
+                Utc::now() - chrono::Duration::minutes(5),
+            );
+
+        let current_kid = &uut_key_set.get_signing_key().kid;
+
+        assert!(uut_key_set.get_verification_key(current_kid).is_some());
+        assert!(uut_key_set.get_verification_key(&retired_kid).is_some());
+        assert!(uut_key_set.get_verification_key(&expired_kid).is_none());
+        assert!(uut_key_set.get_verification_key("unknown").is_none());
+
+        assert_eq!(2, uut_key_set.get_public_jwks().len());
+    }
+
+    #[test]
+    fn will_accept_previous_key_until_configured_time() {
+        let config = AppConfig {
+            jwt_signing_key: "current key".into(),
+            jwt_previous_signing_key: Some("previous key".into()),
+            jwt_previous_key_accepted_until: Some("2000-01-01T00:00:00Z".into()),
+            ..AppConfig::default()
+        };
+
+        let uut_key_set = JwtKeySet::from_config(&config).unwrap();
+
+        let previous_kid = JwtSigningKey::from_secret("previous key").kid;
+
+        assert!(uut_key_set.get_verification_key(&previous_kid).is_none());
+    }
+
+    #[test]
+    fn will_require_accepted_until_for_previous_key() {
+        let config = AppConfig {
+            jwt_signing_key: "current key".into(),
+            jwt_previous_signing_key: Some("previous key".into()),
+            ..AppConfig::default()
+        };
+
+        assert!(JwtKeySet::from_config(&config).is_err());
+    }
+}
@@ -0,0 +1,150 @@
+use std::fmt::{self, Display};
+
+use actix_web::{
+    http::{
+        header::{HeaderValue, WWW_AUTHENTICATE},
+        StatusCode,
+    },
+    HttpResponse, ResponseError,
+};
+use jsonwebtoken::errors::ErrorKind;
+
+use crate::api_error::ApiError;
+
+/// Bearer token authentication error.
+/// Responses carry `WWW-Authenticate` challenge. See [RFC 6750](https://datatracker.ietf.org/doc/html/rfc6750#section-3)
+#[derive(Debug, PartialEq)]
+pub enum TokenError {
+    /// Request has no bearer token
+    Missing,
+    /// Token is not a well-formed JWT
+    Malformed,
+    Expired,
+    NotYetValid,
+    InvalidAudience,
+    InvalidSignature,
+    /// Token was signed with a key that is not known or no longer accepted
+    UnknownKey,
+    Revoked,
+    /// Token failed other validation rules
+    Invalid,
+    /// Token is valid, but it doesn't grant the required role
+    InsufficientRole(String),
+}
+
+impl TokenError {
+    /// RFC 6750 error code. Requests without a token get no error code
+    fn get_error_code(&self) -> Option<&'static str> {
+        match self {
+            Self::Missing => None,
+            Self::InsufficientRole(_) => Some("insufficient_scope"),
+            _ => Some("invalid_token"),
+        }
+    }
+
+    fn get_challenge(&self) -> String {
+        match self.get_error_code() {
+            Some(error_code) => {
+                format!("Bearer error=\"{error_code}\", error_description=\"{self}\"")
+            }
+            None => String::from("Bearer"),
+        }
+    }
+}
+
+impl Display for TokenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Missing => write!(f, "bearer token is missing!"),
+            Self::Malformed => write!(f, "bearer token is malformed!"),
+            Self::Expired => write!(f, "bearer token is expired!"),
+            Self::NotYetValid => write!(f, "bearer token is not valid yet!"),
+            Self::InvalidAudience => write!(f, "bearer token audience is invalid!"),
+            Self::InvalidSignature => write!(f, "bearer token signature is invalid!"),
+            Self::UnknownKey => write!(f, "bearer token signing key is unknown!"),
+            Self::Revoked => write!(f, "bearer token was revoked!"),
+            Self::Invalid => write!(f, "bearer token is invalid!"),
+            Self::InsufficientRole(role) => write!(f, "{role} role is required!"),
+        }
+    }
+}
+
+impl std::error::Error for TokenError {}
+
+impl From<jsonwebtoken::errors::Error> for TokenError {
+    fn from(err: jsonwebtoken::errors::Error) -> Self {
+        match err.kind() {
+            ErrorKind::ExpiredSignature => Self::Expired,
+            ErrorKind::ImmatureSignature => Self::NotYetValid,
+            ErrorKind::InvalidAudience => Self::InvalidAudience,
+            ErrorKind::InvalidSignature => Self::InvalidSignature,
+            ErrorKind::InvalidToken
+            | ErrorKind::Base64(_)
+            | ErrorKind::Json(_)
+            | ErrorKind::Utf8(_) => Self::Malformed,
+            _ => Self::Invalid,
+        }
+    }
+}
+
+impl ResponseError for TokenError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            Self::InsufficientRole(_) => StatusCode::FORBIDDEN,
+            _ => StatusCode::UNAUTHORIZED,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        // body is the same problem details as other api errors
+        let mut response = match self {
+            Self::InsufficientRole(_) => ApiError::Forbidden(self.to_string()),
+            _ => ApiError::Unauthorized(self.to_string()),
+        }
+        .error_response();
+
+        if let Ok(challenge) = HeaderValue::from_str(&self.get_challenge()) {
+            response.headers_mut().insert(WWW_AUTHENTICATE, challenge);
+        }
+
+        response
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn will_return_challenge_with_error_description() {
+        let actual_resp = TokenError::Expired.error_response();
+
+        assert_eq!(StatusCode::UNAUTHORIZED, actual_resp.status());
+        assert_eq!(
+            "Bearer error=\"invalid_token\", error_description=\"bearer token is expired!\"",
+            actual_resp.headers().get(WWW_AUTHENTICATE).unwrap()
+        );
+    }
+
+    #[test]
+    fn will_return_challenge_without_error_on_missing_token() {
+        let actual_resp = TokenError::Missing.error_response();
+
+        assert_eq!(StatusCode::UNAUTHORIZED, actual_resp.status());
+        assert_eq!(
+            "Bearer",
+            actual_resp.headers().get(WWW_AUTHENTICATE).unwrap()
+        );
+    }
+
+    #[test]
+    fn will_return_403_on_insufficient_role() {
+        let actual_resp = TokenError::InsufficientRole("admin".into()).error_response();
+
+        assert_eq!(StatusCode::FORBIDDEN, actual_resp.status());
+        assert_eq!(
+            "Bearer error=\"insufficient_scope\", error_description=\"admin role is required!\"",
+            actual_resp.headers().get(WWW_AUTHENTICATE).unwrap()
+        );
+    }
+}
@@ -0,0 +1,163 @@
+use std::{collections::HashMap, sync::RwLock, time::Duration};
+
+use bson::{doc, DateTime};
+use futures_util::TryStreamExt;
+use mongodb::{options::IndexOptions, Collection, Database, IndexModel};
+use serde::{Deserialize, Serialize};
+
+use crate::api_error::{ApiError, ApiResult};
+
+/// Revocation id covering every token issued to the subject, e.g. to a deleted player.
+/// Token ids are uuids, so they never collide with subject revocation ids
+pub fn get_subject_revocation_id(sub: &str) -> String {
+    format!("sub:{sub}")
+}
+
+/// Revoked access token. Record is removed by the TTL index once the token expires
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct RevokedToken {
+    #[serde(rename = "_id")]
+    pub jti: String,
+    /// Revoked token expiration. Token is rejected by `exp` validation after this date anyway
+    pub expires_at: DateTime,
+}
+
+impl RevokedToken {
+    pub fn get_revoked_token_collection(mongo_database: &Database) -> Collection<RevokedToken> {
+        mongo_database.collection::<RevokedToken>("revoked_tokens")
+    }
+
+    pub async fn create_ttl_index(
+        mongo_database: &Database,
+    ) -> Result<mongodb::results::CreateIndexResult, mongodb::error::Error> {
+        let options = IndexOptions::builder().expire_after(Duration::ZERO).build();
+        let model = IndexModel::builder()
+            .keys(doc! { "expires_at": 1 })
+            .options(options)
+            .build();
+
+        Self::get_revoked_token_collection(mongo_database)
+            .create_index(model)
+            .await
+    }
+
+    /// Revoke token until it expires. Revoking the same token twice is a no-op
+    pub async fn revoke(
+        mongo_database: &Database,
+        jti: &str,
+        expires_at: DateTime,
+    ) -> ApiResult<()> {
+        Self::get_revoked_token_collection(mongo_database)
+            .update_one(
+                doc! { "_id": jti },
+                doc! { "$setOnInsert": { "expires_at": expires_at } },
+            )
+            .upsert(true)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Retrieve revoked tokens that have not expired yet.
+    /// TTL monitor runs periodically, so expired records may still be present in the collection.
+    pub async fn get_active(mongo_database: &Database) -> ApiResult<Vec<Self>> {
+        let revoked_tokens = Self::get_revoked_token_collection(mongo_database)
+            .find(doc! { "expires_at": { "$gt": DateTime::now() } })
+            .await?
+            .try_collect()
+            .await?;
+
+        Ok(revoked_tokens)
+    }
+}
+
+/// In-process copy of the revoked tokens.
+/// Middleware checks this cache only, so token validation never waits on the database.
+/// Cache is synced periodically to pick up tokens revoked by other api instances.
+#[derive(Default)]
+pub struct RevokedTokenCache {
+    /// Revoked token ids with their expiration in unix millis
+    revoked_tokens: RwLock<HashMap<String, i64>>,
+}
+
+impl RevokedTokenCache {
+    pub fn is_revoked(&self, jti: &str) -> bool {
+        self.revoked_tokens
+            .read()
+            .map(|revoked_tokens| revoked_tokens.contains_key(jti))
+            // failing closed would lock every player out, and poisoning means a bug elsewhere
+            .unwrap_or_default()
+    }
+
+    /// Token is revoked by its id, or together with all tokens of its subject
+    pub fn is_token_revoked(&self, jti: &str, sub: &str) -> bool {
+        self.is_revoked(jti) || self.is_revoked(&get_subject_revocation_id(sub))
+    }
+
+    pub fn insert(&self, jti: &str, expires_at: DateTime) -> ApiResult<()> {
+        self.get_revoked_tokens_for_update()?
+            .insert(jti.into(), expires_at.timestamp_millis());
+
+        Ok(())
+    }
+
+    /// Replace cached tokens with the database state, and drop expired tokens
+    pub async fn sync(&self, mongo_database: &Database) -> ApiResult<()> {
+        let active_tokens = RevokedToken::get_active(mongo_database).await?;
+
+        let mut revoked_tokens = self.get_revoked_tokens_for_update()?;
+
+        let now = DateTime::now().timestamp_millis();
+        revoked_tokens.retain(|_, expires_at| *expires_at > now);
+        revoked_tokens.extend(
+            active_tokens
+                .into_iter()
+                .map(|token| (token.jti, token.expires_at.timestamp_millis())),
+        );
+
+        Ok(())
+    }
+
+    fn get_revoked_tokens_for_update(
+        &self,
+    ) -> ApiResult<std::sync::RwLockWriteGuard<'_, HashMap<String, i64>>> {
+        self.revoked_tokens
+            .write()
+            .map_err(|_| ApiError::Internal("revoked token cache lock is poisoned!".into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn will_report_inserted_token_as_revoked() {
+        let uut_cache = RevokedTokenCache::default();
+
+        uut_cache
+            .insert(
+                "revoked_jti",
+                DateTime::from_millis(DateTime::now().timestamp_millis() + 60_000),
+            )
+            .unwrap();
+
+        assert!(uut_cache.is_revoked("revoked_jti"));
+        assert!(!uut_cache.is_revoked("other_jti"));
+    }
+
+    #[test]
+    fn will_report_tokens_of_revoked_subject_as_revoked() {
+        let uut_cache = RevokedTokenCache::default();
+
+        uut_cache
+            .insert(
+                &get_subject_revocation_id("revoked_sub"),
+                DateTime::from_millis(DateTime::now().timestamp_millis() + 60_000),
+            )
+            .unwrap();
+
+        assert!(uut_cache.is_token_revoked("any_jti", "revoked_sub"));
+        assert!(!uut_cache.is_token_revoked("any_jti", "other_sub"));
+    }
+}
@@ -1,9 +1,17 @@
 use std::error::Error;
 
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
 use chrono::Utc;
-use jsonwebtoken::{decode, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use futures_util::{
+    future::{self, BoxFuture},
+    FutureExt as _,
+};
+use jsonwebtoken::{decode, decode_header, encode, jwk::JwkSet, Header, Validation};
+use rand::RngCore;
 use serde::{Deserialize, Serialize};
 
+use super::{signing_key::JwtKeySet, token_error::TokenError};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UserClaims {
     pub sub: String,
@@ -12,6 +20,14 @@ pub struct UserClaims {
     pub exp: usize,
     pub nbf: usize,
     pub iat: usize,
+    /// Unique token id. Used to revoke individual tokens before they expire
+    pub jti: String,
+    /// Player display name
+    #[serde(default)]
+    pub name: String,
+    /// Player roles, e.g. `admin`. Checked by role guards on protected scopes
+    #[serde(default)]
+    pub roles: Vec<String>,
 }
 
 #[derive(Serialize)]
@@ -19,17 +35,31 @@ pub struct JwtToken {
     pub access_token: String,
 }
 
+/// Api token issuer and validator.
+/// Methods return boxed futures, so implementations can fetch remote keys or hit the database
+/// without blocking an actix worker.
 pub trait TokenService: Send + Sync {
     /// Generate new token with expiration
-    fn generate_token(&self, subject: &str) -> Result<JwtToken, Box<dyn Error>>;
+    fn generate_token<'a>(
+        &'a self,
+        subject: &'a str,
+        name: &'a str,
+        roles: &'a [String],
+    ) -> BoxFuture<'a, Result<JwtToken, Box<dyn Error + Send + Sync>>>;
 
     /// Validate token and retrieve token claims
-    fn get_validated_claims(&self, token: &str) -> Result<UserClaims, Box<dyn Error>>;
+    fn get_validated_claims<'a>(
+        &'a self,
+        token: &'a str,
+    ) -> BoxFuture<'a, Result<UserClaims, TokenError>>;
+
+    /// Public keys other services can use to verify api tokens
+    fn get_public_jwks(&self) -> JwkSet;
 }
 
 pub struct JwtTokenService {
     token_lifetime_min: u32,
-    signing_key: String,
+    key_set: JwtKeySet,
     issuer: String,
     audience: String,
     token_validation_rules: Validation,
@@ -37,19 +67,20 @@ pub struct JwtTokenService {
 
 impl JwtTokenService {
     pub fn new(
-        signing_key: &str,
+        key_set: JwtKeySet,
         issuer: &str,
         audience: &str,
         validation_time_skew_sec: u32,
         token_lifetime_min: u32,
     ) -> Self {
-        let mut token_validation = Validation::new(Algorithm::HS256);
+        // algorithm is set per verification key
+        let mut token_validation = Validation::default();
         token_validation.leeway = validation_time_skew_sec as u64;
         token_validation.set_audience(&[audience]);
         token_validation.set_required_spec_claims(&["exp", "aud", "sub"]);
 
         Self {
-            signing_key: signing_key.into(),
+            key_set,
             issuer: issuer.into(),
             audience: audience.into(),
             token_validation_rules: token_validation,
@@ -58,8 +89,13 @@ impl JwtTokenService {
     }
 }
 
-impl TokenService for JwtTokenService {
-    fn generate_token(&self, subject: &str) -> Result<JwtToken, Box<dyn Error>> {
+impl JwtTokenService {
+    fn create_token(
+        &self,
+        subject: &str,
+        name: &str,
+        roles: &[String],
+    ) -> Result<JwtToken, Box<dyn Error + Send + Sync>> {
         let now = Utc::now();
 
         let exp = now
@@ -75,46 +111,112 @@ impl TokenService for JwtTokenService {
             exp,
             nbf: now.timestamp() as usize,
             iat: now.timestamp() as usize,
+            jti: generate_token_id(),
+            name: name.into(),
+            roles: roles.to_vec(),
         };
 
-        let access_token = encode(
-            &Header::default(),
-            &user_claims,
-            &EncodingKey::from_secret(self.signing_key.as_ref()),
-        )?;
+        let signing_key = self.key_set.get_signing_key();
+
+        let mut header = Header::new(signing_key.algorithm);
+        header.kid = Some(signing_key.kid.clone());
+
+        let access_token = encode(&header, &user_claims, signing_key.get_encoding_key())?;
 
         Ok(JwtToken { access_token })
     }
 
-    fn get_validated_claims(&self, token: &str) -> Result<UserClaims, Box<dyn Error>> {
+    fn validate_token(&self, token: &str) -> Result<UserClaims, TokenError> {
+        // tokens are always issued with key id
+        let kid = decode_header(token)?.kid.ok_or(TokenError::UnknownKey)?;
+
+        let verification_key = self
+            .key_set
+            .get_verification_key(&kid)
+            .ok_or(TokenError::UnknownKey)?;
+
+        let mut token_validation = self.token_validation_rules.clone();
+        token_validation.algorithms = vec![verification_key.algorithm];
+
         let decoded_token = decode::<UserClaims>(
             token,
-            &DecodingKey::from_secret(self.signing_key.as_ref()),
-            &self.token_validation_rules,
+            verification_key.get_decoding_key(),
+            &token_validation,
         )?;
 
         Ok(decoded_token.claims)
     }
 }
 
+/// Random 128-bit token id
+fn generate_token_id() -> String {
+    let mut token_id = [0u8; 16];
+    rand::thread_rng().fill_bytes(&mut token_id);
+
+    URL_SAFE_NO_PAD.encode(token_id)
+}
+
+/// Keys are held in memory, so futures are always ready. Used by unit tests as is
+impl TokenService for JwtTokenService {
+    fn generate_token<'a>(
+        &'a self,
+        subject: &'a str,
+        name: &'a str,
+        roles: &'a [String],
+    ) -> BoxFuture<'a, Result<JwtToken, Box<dyn Error + Send + Sync>>> {
+        future::ready(self.create_token(subject, name, roles)).boxed()
+    }
+
+    fn get_validated_claims<'a>(
+        &'a self,
+        token: &'a str,
+    ) -> BoxFuture<'a, Result<UserClaims, TokenError>> {
+        future::ready(self.validate_token(token)).boxed()
+    }
+
+    fn get_public_jwks(&self) -> JwkSet {
+        JwkSet {
+            keys: self.key_set.get_public_jwks(),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use base64::{engine::general_purpose, Engine as _};
+    use jsonwebtoken::{Algorithm, DecodingKey};
     use serde_json::Value;
 
+    use super::super::signing_key::JwtSigningKey;
     use super::*;
 
-    #[test]
-    fn will_generate_valid_token_with_required_claims() {
-        let uut_svc = JwtTokenService::new("secret key", "issuer", "audience", 1, 5);
+    fn create_test_header(uut_svc: &JwtTokenService) -> Header {
+        Header {
+            kid: Some(uut_svc.key_set.get_signing_key().kid.clone()),
+            ..Header::default()
+        }
+    }
 
-        let actual_token = uut_svc.generate_token("test_subject").unwrap();
+    #[actix_web::test]
+    async fn will_generate_valid_token_with_required_claims() {
+        let uut_svc = JwtTokenService::new(
+            JwtKeySet::new(JwtSigningKey::from_secret("secret key")),
+            "issuer",
+            "audience",
+            1,
+            5,
+        );
+
+        let actual_token = uut_svc
+            .generate_token("test_subject", "test player", &[])
+            .await
+            .unwrap();
 
         let token_parts: Vec<&str> = actual_token.access_token.split(".").collect();
 
         assert_eq!(3, token_parts.len());
 
-        let body_bytes = general_purpose::STANDARD
+        let body_bytes = general_purpose::URL_SAFE_NO_PAD
             .decode(token_parts[1])
             .expect("Invalid base64url data");
         let body_str = String::from_utf8(body_bytes).expect("body must be utf8");
@@ -123,25 +225,44 @@ mod tests {
         assert!(body_json.get("aud").is_some());
         assert!(body_json.get("sub").is_some());
         assert!(body_json.get("exp").is_some());
+        assert!(body_json.get("jti").is_some());
     }
 
-    #[test]
-    fn will_decode_valid_token() {
-        let uut_svc = JwtTokenService::new("secret key", "issuer", "audience", 1, 5);
-
-        let token_to_decode = uut_svc.generate_token("test_subject").unwrap();
+    #[actix_web::test]
+    async fn will_decode_valid_token() {
+        let uut_svc = JwtTokenService::new(
+            JwtKeySet::new(JwtSigningKey::from_secret("secret key")),
+            "issuer",
+            "audience",
+            1,
+            5,
+        );
+
+        let token_to_decode = uut_svc
+            .generate_token("test_subject", "test player", &["admin".into()])
+            .await
+            .unwrap();
 
         let actual_claims = uut_svc
             .get_validated_claims(&token_to_decode.access_token)
+            .await
             .unwrap();
 
         assert_eq!("test_subject", actual_claims.sub);
         assert_eq!("audience", actual_claims.aud);
+        assert_eq!("test player", actual_claims.name);
+        assert_eq!(vec!["admin".to_string()], actual_claims.roles);
     }
 
-    #[test]
-    fn will_return_error_on_expired_token() {
-        let uut_svc = JwtTokenService::new("secret key", "issuer", "audience", 1, 5);
+    #[actix_web::test]
+    async fn will_return_error_on_expired_token() {
+        let uut_svc = JwtTokenService::new(
+            JwtKeySet::new(JwtSigningKey::from_secret("secret key")),
+            "issuer",
+            "audience",
+            1,
+            5,
+        );
 
         let now = Utc::now();
 
@@ -158,23 +279,35 @@ mod tests {
             exp,
             nbf: now.timestamp() as usize,
             iat: now.timestamp() as usize,
+            jti: generate_token_id(),
+            name: "test player".into(),
+            roles: vec![],
         };
 
         let token_to_decode = encode(
-            &Header::default(),
+            &create_test_header(&uut_svc),
             &user_claims,
-            &EncodingKey::from_secret(uut_svc.signing_key.as_ref()),
+            uut_svc.key_set.get_signing_key().get_encoding_key(),
         )
         .expect("valid token required");
 
-        let actual_decode_err = uut_svc.get_validated_claims(&token_to_decode).unwrap_err();
+        let actual_decode_err = uut_svc
+            .get_validated_claims(&token_to_decode)
+            .await
+            .unwrap_err();
 
-        assert_eq!("ExpiredSignature", actual_decode_err.to_string());
+        assert_eq!(TokenError::Expired, actual_decode_err);
     }
 
-    #[test]
-    fn will_return_error_on_invalid_audience_token() {
-        let uut_svc = JwtTokenService::new("secret key", "issuer", "audience", 1, 5);
+    #[actix_web::test]
+    async fn will_return_error_on_invalid_audience_token() {
+        let uut_svc = JwtTokenService::new(
+            JwtKeySet::new(JwtSigningKey::from_secret("secret key")),
+            "issuer",
+            "audience",
+            1,
+            5,
+        );
 
         let now = Utc::now();
 
@@ -191,17 +324,196 @@ mod tests {
             exp,
             nbf: now.timestamp() as usize,
             iat: now.timestamp() as usize,
+            jti: generate_token_id(),
+            name: "test player".into(),
+            roles: vec![],
         };
 
         let token_to_decode = encode(
-            &Header::default(),
+            &create_test_header(&uut_svc),
             &user_claims,
-            &EncodingKey::from_secret(uut_svc.signing_key.as_ref()),
+            uut_svc.key_set.get_signing_key().get_encoding_key(),
         )
         .expect("valid token required");
 
-        let actual_decode_err = uut_svc.get_validated_claims(&token_to_decode).unwrap_err();
+        let actual_decode_err = uut_svc
+            .get_validated_claims(&token_to_decode)
+            .await
+            .unwrap_err();
+
+        assert_eq!(TokenError::InvalidAudience, actual_decode_err);
+    }
+
+    #[actix_web::test]
+    async fn will_verify_rsa_signed_token_with_published_key() {
+        let uut_svc = JwtTokenService::new(
+            JwtKeySet::new(
+                JwtSigningKey::from_rsa_pem(include_str!(
+                    "../../tests/fixtures/jwt_test_rsa_key.pem"
+                ))
+                .unwrap(),
+            ),
+            "issuer",
+            "audience",
+            1,
+            5,
+        );
+
+        let actual_token = uut_svc
+            .generate_token("test_subject", "test player", &[])
+            .await
+            .unwrap();
+        let actual_jwks = uut_svc.get_public_jwks();
+
+        assert_eq!(1, actual_jwks.keys.len());
+
+        // other services only have the published public key
+        let mut validation = Validation::new(Algorithm::RS256);
+        validation.set_audience(&["audience"]);
+
+        let actual_claims = decode::<UserClaims>(
+            &actual_token.access_token,
+            &DecodingKey::from_jwk(&actual_jwks.keys[0]).unwrap(),
+            &validation,
+        )
+        .unwrap()
+        .claims;
+
+        assert_eq!("test_subject", actual_claims.sub);
+    }
+
+    #[actix_web::test]
+    async fn will_decode_ec_signed_token() {
+        let uut_svc = JwtTokenService::new(
+            JwtKeySet::new(
+                JwtSigningKey::from_ec_pem(include_str!(
+                    "../../tests/fixtures/jwt_test_ec_key.pem"
+                ))
+                .unwrap(),
+            ),
+            "issuer",
+            "audience",
+            1,
+            5,
+        );
+
+        let token_to_decode = uut_svc
+            .generate_token("test_subject", "test player", &[])
+            .await
+            .unwrap();
+
+        let actual_claims = uut_svc
+            .get_validated_claims(&token_to_decode.access_token)
+            .await
+            .unwrap();
+
+        assert_eq!("test_subject", actual_claims.sub);
+    }
+
+    #[test]
+    fn will_not_publish_hs256_key() {
+        let uut_svc = JwtTokenService::new(
+            JwtKeySet::new(JwtSigningKey::from_secret("secret key")),
+            "issuer",
+            "audience",
+            1,
+            5,
+        );
+
+        assert!(uut_svc.get_public_jwks().keys.is_empty());
+    }
+
+    #[actix_web::test]
+    async fn will_decode_token_signed_with_retired_key() {
+        let retired_svc = JwtTokenService::new(
+            JwtKeySet::new(JwtSigningKey::from_secret("old key")),
+            "issuer",
+            "audience",
+            1,
+            5,
+        );
+
+        let uut_svc = JwtTokenService::new(
+            JwtKeySet::new(JwtSigningKey::from_secret("new key")).with_retired_key(
+                JwtSigningKey::from_secret("old key"),
+                Utc::now() + chrono::Duration::minutes(5),
+            ),
+            "issuer",
+            "audience",
+            1,
+            5,
+        );
+
+        let token_to_decode = retired_svc
+            .generate_token("test_subject", "test player", &[])
+            .await
+            .unwrap();
+
+        let actual_claims = uut_svc
+            .get_validated_claims(&token_to_decode.access_token)
+            .await
+            .unwrap();
+
+        assert_eq!("test_subject", actual_claims.sub);
+    }
+
+    #[actix_web::test]
+    async fn will_return_error_on_token_signed_with_unknown_key() {
+        let other_svc = JwtTokenService::new(
+            JwtKeySet::new(JwtSigningKey::from_secret("other key")),
+            "issuer",
+            "audience",
+            1,
+            5,
+        );
+
+        let uut_svc = JwtTokenService::new(
+            JwtKeySet::new(JwtSigningKey::from_secret("secret key")),
+            "issuer",
+            "audience",
+            1,
+            5,
+        );
+
+        let token_to_decode = other_svc
+            .generate_token("test_subject", "test player", &[])
+            .await
+            .unwrap();
+
+        let actual_decode_err = uut_svc
+            .get_validated_claims(&token_to_decode.access_token)
+            .await
+            .unwrap_err();
+
+        assert_eq!(TokenError::UnknownKey, actual_decode_err);
+    }
+
+    #[actix_web::test]
+    async fn will_return_error_on_tampered_signature() {
+        let uut_svc = JwtTokenService::new(
+            JwtKeySet::new(JwtSigningKey::from_secret("secret key")),
+            "issuer",
+            "audience",
+            1,
+            5,
+        );
+
+        let valid_token = uut_svc
+            .generate_token("test_subject", "test player", &[])
+            .await
+            .unwrap();
+
+        let (unsigned_token, _) = valid_token.access_token.rsplit_once('.').unwrap();
+        let tampered_token = format!(
+            "{unsigned_token}.{}",
+            general_purpose::URL_SAFE_NO_PAD.encode([0u8; 32])
+        );
+
+        let actual_decode_err = uut_svc
+            .get_validated_claims(&tampered_token)
+            .await
+            .unwrap_err();
 
-        assert_eq!("InvalidAudience", actual_decode_err.to_string());
+        assert_eq!(TokenError::InvalidSignature, actual_decode_err);
     }
 }
@@ -0,0 +1,270 @@
+use std::{collections::HashSet, error::Error, fs};
+
+use serde::Deserialize;
+
+use super::{achievements::BUILT_IN_ACHIEVEMENT_IDS, license_plates::SpottedPlate};
+
+/// Achievement definitions shipped with the api. Used when no definitions file is configured.
+const DEFAULT_DEFINITIONS_JSON: &str = include_str!("default_achievements.json");
+
+/// Data-driven region achievement.
+/// Achievement is reached when at least `min_count` plates from `plates` were spotted.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+pub struct AchievementDefinition {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub plates: Vec<SpottedPlate>,
+    /// Number of plates required to reach the achievement. Defaults to all plates
+    pub min_count: Option<usize>,
+    pub points: u32,
+}
+
+#[derive(Deserialize)]
+struct AchievementDefinitionsFile {
+    achievements: Vec<AchievementDefinition>,
+}
+
+impl AchievementDefinition {
+    /// Number of plates required to reach the achievement
+    pub fn required_count(&self) -> usize {
+        self.min_count.unwrap_or(self.plates.len())
+    }
+
+    fn validate(&self) -> Result<(), Box<dyn Error>> {
+        if self.id.trim().is_empty() || self.name.trim().is_empty() {
+            return Err("achievement id and name are required!".into());
+        }
+
+        if self.plates.is_empty() {
+            return Err(format!("achievement '{}' has no plates!", self.id).into());
+        }
+
+        if let Some(invalid_plate) = self
+            .plates
+            .iter()
+            .find(|plate| !plate.is_valid_game_plate())
+        {
+            return Err(format!(
+                "achievement '{}' has invalid plate {:?}-{:?}!",
+                self.id, invalid_plate.country, invalid_plate.state_or_province
+            )
+            .into());
+        }
+
+        let unique_plates: HashSet<_> = self.plates.iter().collect();
+        if unique_plates.len() != self.plates.len() {
+            return Err(format!("achievement '{}' has duplicate plates!", self.id).into());
+        }
+
+        let required_count = self.required_count();
+        if required_count == 0 || required_count > self.plates.len() {
+            return Err(format!(
+                "achievement '{}' min_count must be between 1 and {}!",
+                self.id,
+                self.plates.len()
+            )
+            .into());
+        }
+
+        Ok(())
+    }
+}
+
+/// Parse and validate achievement definitions from JSON
+pub fn parse_definitions(json: &str) -> Result<Vec<AchievementDefinition>, Box<dyn Error>> {
+    let definitions_file: AchievementDefinitionsFile = serde_json::from_str(json)?;
+
+    let mut unique_ids = HashSet::new();
+
+    for definition in &definitions_file.achievements {
+        definition.validate()?;
+
+        // built-in achievements are always scored, so a definition with the same id would be scored twice
+        if BUILT_IN_ACHIEVEMENT_IDS.contains(&definition.id.as_str()) {
+            return Err(format!(
+                "achievement id '{}' is reserved for a built-in achievement!",
+                definition.id
+            )
+            .into());
+        }
+
+        if !unique_ids.insert(definition.id.as_str()) {
+            return Err(format!("achievement id '{}' is not unique!", definition.id).into());
+        }
+    }
+
+    Ok(definitions_file.achievements)
+}
+
+/// Load and validate achievement definitions from JSON file
+pub fn load_definitions_from_file(
+    path: &str,
+) -> Result<Vec<AchievementDefinition>, Box<dyn Error>> {
+    let json = fs::read_to_string(path)
+        .map_err(|err| format!("failed to read achievements file '{path}': {err}"))?;
+
+    parse_definitions(&json)
+}
+
+/// Achievement definitions shipped with the api
+pub fn default_definitions() -> Vec<AchievementDefinition> {
+    parse_definitions(DEFAULT_DEFINITIONS_JSON).expect("default achievements must be valid")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn will_parse_default_definitions() {
+        let actual_definitions = default_definitions();
+
+        let actual_ids: Vec<&str> = actual_definitions
+            .iter()
+            .map(|definition| definition.id.as_str())
+            .collect();
+
+        assert_eq!(
+            vec!["west_coast", "maritimes", "prairies", "true_north"],
+            actual_ids
+        );
+    }
+
+    #[test]
+    fn will_parse_definition_with_min_count() {
+        let json = r#"{
+            "achievements": [{
+                "id": "pacific_northwest",
+                "name": "Pacific Northwest",
+                "plates": [
+                    { "country": "US", "state_or_province": "WA" },
+                    { "country": "US", "state_or_province": "OR" },
+                    { "country": "CA", "state_or_province": "BC" }
+                ],
+                "min_count": 2,
+                "points": 15
+            }]
+        }"#;
+
+        let actual_definitions = parse_definitions(json).unwrap();
+
+        assert_eq!(1, actual_definitions.len());
+        assert_eq!(2, actual_definitions[0].required_count());
+        assert_eq!(15, actual_definitions[0].points);
+        assert_eq!("", actual_definitions[0].description);
+    }
+
+    #[test]
+    fn will_return_error_on_unknown_state() {
+        let json = r#"{
+            "achievements": [{
+                "id": "unknown",
+                "name": "Unknown",
+                "plates": [{ "country": "US", "state_or_province": "XX" }],
+                "points": 15
+            }]
+        }"#;
+
+        let actual_err = parse_definitions(json).unwrap_err();
+
+        assert!(actual_err.to_string().contains("unknown variant `XX`"));
+    }
+
+    #[test]
+    fn will_return_error_on_invalid_country_state_combination() {
+        let json = r#"{
+            "achievements": [{
+                "id": "invalid",
+                "name": "Invalid",
+                "plates": [{ "country": "US", "state_or_province": "AB" }],
+                "points": 15
+            }]
+        }"#;
+
+        let actual_err = parse_definitions(json).unwrap_err();
+
+        assert_eq!(
+            "achievement 'invalid' has invalid plate US-AB!",
+            actual_err.to_string()
+        );
+    }
+
+    #[test]
+    fn will_return_error_on_min_count_over_plates() {
+        let json = r#"{
+            "achievements": [{
+                "id": "too_many",
+                "name": "Too Many",
+                "plates": [{ "country": "US", "state_or_province": "WA" }],
+                "min_count": 2,
+                "points": 15
+            }]
+        }"#;
+
+        let actual_err = parse_definitions(json).unwrap_err();
+
+        assert_eq!(
+            "achievement 'too_many' min_count must be between 1 and 1!",
+            actual_err.to_string()
+        );
+    }
+
+    #[test]
+    fn will_return_error_on_duplicate_ids() {
+        let json = r#"{
+            "achievements": [
+                {
+                    "id": "same",
+                    "name": "Same",
+                    "plates": [{ "country": "US", "state_or_province": "WA" }],
+                    "points": 15
+                },
+                {
+                    "id": "same",
+                    "name": "Same Again",
+                    "plates": [{ "country": "US", "state_or_province": "OR" }],
+                    "points": 15
+                }
+            ]
+        }"#;
+
+        let actual_err = parse_definitions(json).unwrap_err();
+
+        assert_eq!(
+            "achievement id 'same' is not unique!",
+            actual_err.to_string()
+        );
+    }
+
+    #[test]
+    fn will_return_error_on_built_in_id() {
+        let json = r#"{
+            "achievements": [
+                {
+                    "id": "coast_to_coast",
+                    "name": "West Coast",
+                    "plates": [{ "country": "US", "state_or_province": "WA" }],
+                    "points": 15
+                }
+            ]
+        }"#;
+
+        let actual_err = parse_definitions(json).unwrap_err();
+
+        assert_eq!(
+            "achievement id 'coast_to_coast' is reserved for a built-in achievement!",
+            actual_err.to_string()
+        );
+    }
+
+    #[test]
+    fn will_return_error_on_missing_file() {
+        let actual_err = load_definitions_from_file("does_not_exist.json").unwrap_err();
+
+        assert!(actual_err
+            .to_string()
+            .starts_with("failed to read achievements file 'does_not_exist.json'"));
+    }
+}
@@ -0,0 +1,738 @@
+use std::collections::{HashSet, VecDeque};
+use std::error::Error;
+use std::sync::LazyLock;
+
+use super::achievement_definitions::{self, AchievementDefinition};
+use super::license_plate_enums::{Country, StateOrProvince};
+use super::license_plates::{SpottedPlate, VALID_GAME_PLATES_WITH_BORDERS};
+
+/// Minimum number of connected plates required for the `Longest Chain` bonus
+const LONGEST_CHAIN_MIN_SIZE: usize = 5;
+/// `Longest Chain` bonus points awarded per plate in the chain
+const LONGEST_CHAIN_POINTS_PER_PLATE: u32 = 2;
+const COAST_TO_COAST_POINTS: u32 = 100;
+
+/// Game achievement (bonus) rule.
+/// Each achievement is evaluated independently over the set of unique spotted plates.
+#[allow(dead_code)]
+pub trait Achievement: Send + Sync {
+    /// Stable achievement identifier
+    fn id(&self) -> &str;
+
+    /// Achievement name displayed to players
+    fn name(&self) -> &str;
+
+    fn description(&self) -> &str;
+
+    /// Check if achievement was reached, and return awarded bonus points
+    fn evaluate(&self, plates: &HashSet<&SpottedPlate>) -> (bool, u32);
+}
+
+/// Ordered collection of achievements used for game scoring
+pub struct AchievementRegistry {
+    achievements: Vec<Box<dyn Achievement>>,
+}
+
+#[allow(dead_code)]
+impl AchievementRegistry {
+    /// Create registry without any achievements
+    pub fn empty() -> Self {
+        Self {
+            achievements: Vec::new(),
+        }
+    }
+
+    pub fn with_achievement(mut self, achievement: Box<dyn Achievement>) -> Self {
+        self.achievements.push(achievement);
+        self
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &dyn Achievement> {
+        self.achievements.iter().map(Box::as_ref)
+    }
+}
+
+impl Default for AchievementRegistry {
+    /// Built-in border-graph achievements and default region achievements
+    fn default() -> Self {
+        Self::from_definitions(achievement_definitions::default_definitions())
+    }
+}
+
+#[allow(dead_code)]
+impl AchievementRegistry {
+    /// Create registry with built-in border-graph achievements
+    /// followed by region achievements from the supplied definitions
+    pub fn from_definitions(definitions: Vec<AchievementDefinition>) -> Self {
+        definitions.into_iter().fold(
+            Self::empty()
+                .with_achievement(Box::new(CoastToCoastAchievement))
+                .with_achievement(Box::new(LongestChainAchievement)),
+            |registry, definition| {
+                registry.with_achievement(Box::new(RegionAchievement::from(definition)))
+            },
+        )
+    }
+
+    /// Create registry from validated achievement definitions file
+    pub fn load_from_file(path: &str) -> Result<Self, Box<dyn Error>> {
+        let definitions = achievement_definitions::load_definitions_from_file(path)?;
+
+        Ok(Self::from_definitions(definitions))
+    }
+}
+
+/// Region achievement is reached when enough plates from the region were spotted
+#[allow(dead_code)]
+pub struct RegionAchievement {
+    id: String,
+    name: String,
+    description: String,
+    region: HashSet<SpottedPlate>,
+    min_count: usize,
+    points: u32,
+}
+
+impl From<AchievementDefinition> for RegionAchievement {
+    fn from(definition: AchievementDefinition) -> Self {
+        let min_count = definition.required_count();
+
+        Self {
+            id: definition.id,
+            name: definition.name,
+            description: definition.description,
+            region: definition.plates.into_iter().collect(),
+            min_count,
+            points: definition.points,
+        }
+    }
+}
+
+impl Achievement for RegionAchievement {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    fn evaluate(&self, plates: &HashSet<&SpottedPlate>) -> (bool, u32) {
+        let spotted_count = self
+            .region
+            .iter()
+            .filter(|plate| plates.contains(plate))
+            .count();
+
+        if spotted_count >= self.min_count {
+            return (true, self.points);
+        }
+
+        (false, 0)
+    }
+}
+
+type PlateKey = (Country, StateOrProvince);
+
+static ATLANTIC_COAST_STATES: LazyLock<HashSet<PlateKey>> = LazyLock::new(|| {
+    HashSet::from([
+        (Country::US, StateOrProvince::ME),
+        (Country::US, StateOrProvince::NH),
+        (Country::US, StateOrProvince::MA),
+        (Country::US, StateOrProvince::RI),
+        (Country::US, StateOrProvince::CT),
+        (Country::US, StateOrProvince::NY),
+        (Country::US, StateOrProvince::NJ),
+        (Country::US, StateOrProvince::DE),
+        (Country::US, StateOrProvince::MD),
+        (Country::US, StateOrProvince::VA),
+        (Country::US, StateOrProvince::NC),
+        (Country::US, StateOrProvince::SC),
+        (Country::US, StateOrProvince::GA),
+        (Country::US, StateOrProvince::FL),
+        (Country::CA, StateOrProvince::QC),
+        (Country::CA, StateOrProvince::NB),
+        (Country::CA, StateOrProvince::NS),
+        (Country::CA, StateOrProvince::PE),
+        (Country::CA, StateOrProvince::NL),
+    ])
+});
+
+static PACIFIC_COAST_STATES: LazyLock<HashSet<PlateKey>> = LazyLock::new(|| {
+    HashSet::from([
+        (Country::US, StateOrProvince::WA),
+        (Country::US, StateOrProvince::OR),
+        (Country::US, StateOrProvince::CA),
+        (Country::US, StateOrProvince::AK),
+        (Country::US, StateOrProvince::HI),
+        (Country::CA, StateOrProvince::BC),
+    ])
+});
+
+/// Split spotted plates into groups of plates connected through shared borders.
+/// Plates that are not part of the game border map are ignored.
+fn get_connected_plate_groups(plates: &HashSet<&SpottedPlate>) -> Vec<HashSet<PlateKey>> {
+    let spotted_keys: HashSet<PlateKey> = plates
+        .iter()
+        .map(|plate| (plate.country, plate.state_or_province))
+        .filter(|key| VALID_GAME_PLATES_WITH_BORDERS.contains_key(key))
+        .collect();
+
+    let mut visited: HashSet<PlateKey> = HashSet::new();
+    let mut groups = Vec::new();
+
+    for start in &spotted_keys {
+        if visited.contains(start) {
+            continue;
+        }
+
+        // breadth-first walk over spotted neighbors only
+        let mut group = HashSet::from([*start]);
+        let mut queue = VecDeque::from([*start]);
+        visited.insert(*start);
+
+        while let Some(current) = queue.pop_front() {
+            let borders = VALID_GAME_PLATES_WITH_BORDERS
+                .get(&current)
+                .into_iter()
+                .flatten();
+
+            for neighbor in borders {
+                if spotted_keys.contains(neighbor) && visited.insert(*neighbor) {
+                    group.insert(*neighbor);
+                    queue.push_back(*neighbor);
+                }
+            }
+        }
+
+        groups.push(group);
+    }
+
+    groups
+}
+
+pub const COAST_TO_COAST_ID: &str = "coast_to_coast";
+pub const LONGEST_CHAIN_ID: &str = "longest_chain";
+
+/// Ids of achievements that are always registered. Definitions can't reuse them
+pub const BUILT_IN_ACHIEVEMENT_IDS: [&str; 2] = [COAST_TO_COAST_ID, LONGEST_CHAIN_ID];
+
+/// `Coast to Coast` is achieved when an Atlantic and a Pacific plate
+/// are connected by a chain of spotted bordering plates
+pub struct CoastToCoastAchievement;
+
+impl Achievement for CoastToCoastAchievement {
+    fn id(&self) -> &str {
+        COAST_TO_COAST_ID
+    }
+
+    fn name(&self) -> &str {
+        "Coast to Coast"
+    }
+
+    fn description(&self) -> &str {
+        "Connect an Atlantic and a Pacific plate through bordering spotted plates"
+    }
+
+    fn evaluate(&self, plates: &HashSet<&SpottedPlate>) -> (bool, u32) {
+        let is_achieved = get_connected_plate_groups(plates).iter().any(|group| {
+            !group.is_disjoint(&ATLANTIC_COAST_STATES) && !group.is_disjoint(&PACIFIC_COAST_STATES)
+        });
+
+        if is_achieved {
+            return (true, COAST_TO_COAST_POINTS);
+        }
+
+        (false, 0)
+    }
+}
+
+/// `Longest Chain` bonus scales with the size of the largest group of connected spotted plates
+pub struct LongestChainAchievement;
+
+impl Achievement for LongestChainAchievement {
+    fn id(&self) -> &str {
+        LONGEST_CHAIN_ID
+    }
+
+    fn name(&self) -> &str {
+        "Longest Chain"
+    }
+
+    fn description(&self) -> &str {
+        "Spot a chain of bordering plates"
+    }
+
+    fn evaluate(&self, plates: &HashSet<&SpottedPlate>) -> (bool, u32) {
+        let longest_chain = get_connected_plate_groups(plates)
+            .iter()
+            .map(HashSet::len)
+            .max()
+            .unwrap_or(0);
+
+        if longest_chain >= LONGEST_CHAIN_MIN_SIZE {
+            return (true, longest_chain as u32 * LONGEST_CHAIN_POINTS_PER_PLATE);
+        }
+
+        (false, 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_default_achievement(id: &str) -> Box<dyn Achievement> {
+        let definition = achievement_definitions::default_definitions()
+            .into_iter()
+            .find(|definition| definition.id == id)
+            .expect("default achievement must exist");
+
+        Box::new(RegionAchievement::from(definition))
+    }
+
+    fn to_plates(country: Country, states: &[StateOrProvince]) -> HashSet<SpottedPlate> {
+        states
+            .iter()
+            .map(|state_or_province| SpottedPlate {
+                country,
+                state_or_province: *state_or_province,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn will_return_west_coast_bonus_when_achieved_exactly() {
+        let spotted_plates = HashSet::from([
+            &SpottedPlate {
+                country: Country::US,
+                state_or_province: StateOrProvince::CA,
+            },
+            &SpottedPlate {
+                country: Country::US,
+                state_or_province: StateOrProvince::OR,
+            },
+            &SpottedPlate {
+                country: Country::US,
+                state_or_province: StateOrProvince::WA,
+            },
+        ]);
+
+        let (actual_is_achieved, actual_score) =
+            get_default_achievement("west_coast").evaluate(&spotted_plates);
+
+        assert!(actual_is_achieved);
+        assert_eq!(30, actual_score);
+    }
+
+    #[test]
+    fn will_return_west_coast_bonus_when_achieved_with_other_states() {
+        let spotted_plates = HashSet::from([
+            &SpottedPlate {
+                country: Country::US,
+                state_or_province: StateOrProvince::CA,
+            },
+            &SpottedPlate {
+                country: Country::US,
+                state_or_province: StateOrProvince::OR,
+            },
+            &SpottedPlate {
+                country: Country::US,
+                state_or_province: StateOrProvince::WA,
+            },
+            &SpottedPlate {
+                country: Country::US,
+                state_or_province: StateOrProvince::NV,
+            },
+        ]);
+
+        let (actual_is_achieved, actual_score) =
+            get_default_achievement("west_coast").evaluate(&spotted_plates);
+
+        assert!(actual_is_achieved);
+        assert_eq!(30, actual_score);
+    }
+
+    #[test]
+    fn will_not_return_west_coast_bonus_when_not_achieved() {
+        let spotted_plates = HashSet::from([
+            &SpottedPlate {
+                country: Country::US,
+                state_or_province: StateOrProvince::CA,
+            },
+            &SpottedPlate {
+                country: Country::US,
+                state_or_province: StateOrProvince::OR,
+            },
+        ]);
+
+        let (actual_is_achieved, actual_score) =
+            get_default_achievement("west_coast").evaluate(&spotted_plates);
+
+        assert!(!actual_is_achieved);
+        assert_eq!(0, actual_score);
+    }
+
+    #[test]
+    fn will_not_return_west_coast_bonus_when_not_spots() {
+        let spotted_plates = HashSet::from([]);
+
+        let (actual_is_achieved, actual_score) =
+            get_default_achievement("west_coast").evaluate(&spotted_plates);
+
+        assert!(!actual_is_achieved);
+        assert_eq!(0, actual_score);
+    }
+
+    fn to_spotted_plates(states: &[StateOrProvince]) -> Vec<SpottedPlate> {
+        states
+            .iter()
+            .map(|state_or_province| SpottedPlate {
+                country: Country::US,
+                state_or_province: *state_or_province,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn will_return_connected_plate_groups() {
+        let spotted_plates = to_spotted_plates(&[
+            StateOrProvince::CA,
+            StateOrProvince::OR,
+            StateOrProvince::NV,
+            StateOrProvince::NY,
+            StateOrProvince::NJ,
+            StateOrProvince::HI,
+        ]);
+        let plates_hash: HashSet<_> = spotted_plates.iter().collect();
+
+        let mut actual_group_sizes: Vec<usize> = get_connected_plate_groups(&plates_hash)
+            .iter()
+            .map(HashSet::len)
+            .collect();
+        actual_group_sizes.sort();
+
+        assert_eq!(vec![1, 2, 3], actual_group_sizes);
+    }
+
+    #[test]
+    fn will_ignore_invalid_plates_in_connected_plate_groups() {
+        let spotted_plates = [SpottedPlate {
+            country: Country::US,
+            state_or_province: StateOrProvince::AB,
+        }];
+        let plates_hash: HashSet<_> = spotted_plates.iter().collect();
+
+        let actual_groups = get_connected_plate_groups(&plates_hash);
+
+        assert!(actual_groups.is_empty());
+    }
+
+    #[test]
+    fn will_return_coast_to_coast_bonus_when_connected() {
+        let spotted_plates = to_spotted_plates(&[
+            StateOrProvince::WA,
+            StateOrProvince::ID,
+            StateOrProvince::MT,
+            StateOrProvince::ND,
+            StateOrProvince::MN,
+            StateOrProvince::WI,
+            StateOrProvince::MI,
+            StateOrProvince::OH,
+            StateOrProvince::PA,
+            StateOrProvince::NY,
+        ]);
+        let plates_hash: HashSet<_> = spotted_plates.iter().collect();
+
+        let (actual_is_achieved, actual_score) = CoastToCoastAchievement.evaluate(&plates_hash);
+
+        assert!(actual_is_achieved);
+        assert_eq!(COAST_TO_COAST_POINTS, actual_score);
+    }
+
+    #[test]
+    fn will_not_return_coast_to_coast_bonus_when_not_connected() {
+        let spotted_plates = to_spotted_plates(&[
+            StateOrProvince::WA,
+            StateOrProvince::ID,
+            StateOrProvince::MT,
+            StateOrProvince::MN,
+            StateOrProvince::WI,
+            StateOrProvince::MI,
+            StateOrProvince::OH,
+            StateOrProvince::PA,
+            StateOrProvince::NY,
+        ]);
+        let plates_hash: HashSet<_> = spotted_plates.iter().collect();
+
+        let (actual_is_achieved, actual_score) = CoastToCoastAchievement.evaluate(&plates_hash);
+
+        assert!(!actual_is_achieved);
+        assert_eq!(0, actual_score);
+    }
+
+    #[test]
+    fn will_return_longest_chain_bonus_scaled_by_chain_size() {
+        let spotted_plates = to_spotted_plates(&[
+            StateOrProvince::CA,
+            StateOrProvince::OR,
+            StateOrProvince::WA,
+            StateOrProvince::ID,
+            StateOrProvince::NV,
+            StateOrProvince::UT,
+            StateOrProvince::NY,
+            StateOrProvince::NJ,
+        ]);
+        let plates_hash: HashSet<_> = spotted_plates.iter().collect();
+
+        let (actual_is_achieved, actual_score) = LongestChainAchievement.evaluate(&plates_hash);
+
+        assert!(actual_is_achieved);
+        assert_eq!(6 * LONGEST_CHAIN_POINTS_PER_PLATE, actual_score);
+    }
+
+    #[test]
+    fn will_not_return_longest_chain_bonus_when_chain_too_short() {
+        let spotted_plates = to_spotted_plates(&[
+            StateOrProvince::CA,
+            StateOrProvince::OR,
+            StateOrProvince::WA,
+            StateOrProvince::ID,
+            StateOrProvince::NY,
+        ]);
+        let plates_hash: HashSet<_> = spotted_plates.iter().collect();
+
+        let (actual_is_achieved, actual_score) = LongestChainAchievement.evaluate(&plates_hash);
+
+        assert!(!actual_is_achieved);
+        assert_eq!(0, actual_score);
+    }
+
+    #[test]
+    fn will_return_maritimes_bonus_when_achieved() {
+        let spotted_plates = to_plates(
+            Country::CA,
+            &[
+                StateOrProvince::NB,
+                StateOrProvince::NS,
+                StateOrProvince::PE,
+            ],
+        );
+        let plates_hash: HashSet<_> = spotted_plates.iter().collect();
+
+        let (actual_is_achieved, actual_score) =
+            get_default_achievement("maritimes").evaluate(&plates_hash);
+
+        assert!(actual_is_achieved);
+        assert_eq!(25, actual_score);
+    }
+
+    #[test]
+    fn will_not_return_maritimes_bonus_for_us_plates() {
+        let spotted_plates = [
+            SpottedPlate {
+                country: Country::US,
+                state_or_province: StateOrProvince::NB,
+            },
+            SpottedPlate {
+                country: Country::CA,
+                state_or_province: StateOrProvince::NS,
+            },
+            SpottedPlate {
+                country: Country::CA,
+                state_or_province: StateOrProvince::PE,
+            },
+        ];
+        let plates_hash: HashSet<_> = spotted_plates.iter().collect();
+
+        let (actual_is_achieved, actual_score) =
+            get_default_achievement("maritimes").evaluate(&plates_hash);
+
+        assert!(!actual_is_achieved);
+        assert_eq!(0, actual_score);
+    }
+
+    #[test]
+    fn will_return_prairies_bonus_when_achieved() {
+        let spotted_plates = to_plates(
+            Country::CA,
+            &[
+                StateOrProvince::AB,
+                StateOrProvince::SK,
+                StateOrProvince::MB,
+            ],
+        );
+        let plates_hash: HashSet<_> = spotted_plates.iter().collect();
+
+        let (actual_is_achieved, actual_score) =
+            get_default_achievement("prairies").evaluate(&plates_hash);
+
+        assert!(actual_is_achieved);
+        assert_eq!(25, actual_score);
+    }
+
+    #[test]
+    fn will_return_all_canada_bonus_only_when_every_plate_spotted() {
+        let mut spotted_plates: Vec<_> = VALID_GAME_PLATES_WITH_BORDERS
+            .keys()
+            .filter(|(country, _)| *country == Country::CA)
+            .map(|(country, state_or_province)| SpottedPlate {
+                country: *country,
+                state_or_province: *state_or_province,
+            })
+            .collect();
+        let plates_hash: HashSet<_> = spotted_plates.iter().collect();
+
+        let (actual_is_achieved, actual_score) =
+            get_default_achievement("true_north").evaluate(&plates_hash);
+
+        assert_eq!(13, spotted_plates.len());
+        assert!(actual_is_achieved);
+        assert_eq!(150, actual_score);
+
+        spotted_plates.pop();
+        let plates_hash: HashSet<_> = spotted_plates.iter().collect();
+
+        let (actual_is_achieved, actual_score) =
+            get_default_achievement("true_north").evaluate(&plates_hash);
+
+        assert!(!actual_is_achieved);
+        assert_eq!(0, actual_score);
+    }
+
+    #[test]
+    fn will_return_coast_to_coast_bonus_through_canada() {
+        let spotted_plates = to_plates(
+            Country::CA,
+            &[
+                StateOrProvince::BC,
+                StateOrProvince::AB,
+                StateOrProvince::SK,
+                StateOrProvince::MB,
+                StateOrProvince::ON,
+                StateOrProvince::QC,
+            ],
+        );
+        let plates_hash: HashSet<_> = spotted_plates.iter().collect();
+
+        let (actual_is_achieved, actual_score) = CoastToCoastAchievement.evaluate(&plates_hash);
+
+        assert!(actual_is_achieved);
+        assert_eq!(COAST_TO_COAST_POINTS, actual_score);
+    }
+
+    #[test]
+    fn will_return_coast_to_coast_bonus_across_the_border() {
+        let spotted_plates = [
+            SpottedPlate {
+                country: Country::US,
+                state_or_province: StateOrProvince::WA,
+            },
+            SpottedPlate {
+                country: Country::CA,
+                state_or_province: StateOrProvince::BC,
+            },
+            SpottedPlate {
+                country: Country::CA,
+                state_or_province: StateOrProvince::AB,
+            },
+            SpottedPlate {
+                country: Country::US,
+                state_or_province: StateOrProvince::MT,
+            },
+            SpottedPlate {
+                country: Country::US,
+                state_or_province: StateOrProvince::ND,
+            },
+            SpottedPlate {
+                country: Country::CA,
+                state_or_province: StateOrProvince::MB,
+            },
+            SpottedPlate {
+                country: Country::CA,
+                state_or_province: StateOrProvince::ON,
+            },
+            SpottedPlate {
+                country: Country::US,
+                state_or_province: StateOrProvince::NY,
+            },
+        ];
+        let plates_hash: HashSet<_> = spotted_plates.iter().collect();
+
+        let (actual_is_achieved, _) = CoastToCoastAchievement.evaluate(&plates_hash);
+
+        assert!(actual_is_achieved);
+    }
+
+    #[test]
+    fn will_register_achievements_in_order() {
+        let uut_registry = AchievementRegistry::empty()
+            .with_achievement(Box::new(LongestChainAchievement))
+            .with_achievement(get_default_achievement("west_coast"));
+
+        let actual_ids: Vec<&str> = uut_registry
+            .iter()
+            .map(|achievement| achievement.id())
+            .collect();
+
+        assert_eq!(vec!["longest_chain", "west_coast"], actual_ids);
+    }
+
+    #[test]
+    fn will_return_region_bonus_when_min_count_reached() {
+        let uut_achievement = RegionAchievement::from(AchievementDefinition {
+            id: "pacific_northwest".into(),
+            name: "Pacific Northwest".into(),
+            description: String::default(),
+            plates: vec![
+                SpottedPlate {
+                    country: Country::US,
+                    state_or_province: StateOrProvince::WA,
+                },
+                SpottedPlate {
+                    country: Country::US,
+                    state_or_province: StateOrProvince::OR,
+                },
+                SpottedPlate {
+                    country: Country::CA,
+                    state_or_province: StateOrProvince::BC,
+                },
+            ],
+            min_count: Some(2),
+            points: 15,
+        });
+
+        let spotted_plates = to_plates(Country::US, &[StateOrProvince::WA, StateOrProvince::OR]);
+        let plates_hash: HashSet<_> = spotted_plates.iter().collect();
+
+        let (actual_is_achieved, actual_score) = uut_achievement.evaluate(&plates_hash);
+
+        assert!(actual_is_achieved);
+        assert_eq!(15, actual_score);
+
+        let spotted_plates = to_plates(Country::US, &[StateOrProvince::WA]);
+        let plates_hash: HashSet<_> = spotted_plates.iter().collect();
+
+        let (actual_is_achieved, actual_score) = uut_achievement.evaluate(&plates_hash);
+
+        assert!(!actual_is_achieved);
+        assert_eq!(0, actual_score);
+    }
+
+    #[test]
+    fn will_have_unique_default_achievement_ids() {
+        let uut_registry = AchievementRegistry::default();
+
+        let actual_ids: Vec<&str> = uut_registry
+            .iter()
+            .map(|achievement| achievement.id())
+            .collect();
+        let actual_unique_ids: HashSet<&str> = actual_ids.iter().copied().collect();
+
+        assert_eq!(actual_ids.len(), actual_unique_ids.len());
+    }
+}
@@ -0,0 +1,545 @@
+use std::collections::HashSet;
+
+use bson::{doc, oid::ObjectId};
+use futures_util::TryStreamExt;
+use mongodb::{
+    bson::DateTime,
+    options::{FindOneAndUpdateOptions, FindOptions, ReturnDocument},
+    ClientSession, Collection, Database,
+};
+use serde::{self, Deserialize, Serialize};
+
+use crate::api_error::{ApiError, ApiResult};
+
+use super::{license_plates::SpottedPlate, plate_spot::PlateSpot, player::Player};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum GameStatus {
+    Active,
+    Ended,
+}
+
+#[allow(dead_code)]
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Game {
+    #[serde(rename = "_id")]
+    pub id: ObjectId,
+
+    /// Player that created the game. Only owner can end the game
+    pub owner_id: ObjectId,
+    /// Players allowed to spot plates in this game (owner included)
+    #[serde(default)]
+    pub participants: HashSet<ObjectId>,
+    /// Players with pending invitations to this game
+    #[serde(default)]
+    pub invited_players: HashSet<ObjectId>,
+
+    /// Plate spots in the order they were recorded. Game holds at most one spot per plate
+    #[serde(default)]
+    pub spots: Vec<PlateSpot>,
+
+    pub status: GameStatus,
+    pub date_created: DateTime,
+    pub date_ended: Option<DateTime>,
+}
+
+#[allow(dead_code)]
+impl Game {
+    pub fn get_game_collection(mongo_database: &Database) -> Collection<Game> {
+        mongo_database.collection::<Game>("games")
+    }
+
+    /// Create new active game owned by the supplied player,
+    /// and record it in the player's `games_owned` set
+    pub async fn create_new_game(mongo_database: &Database, owner_id: ObjectId) -> ApiResult<Self> {
+        let new_game = Self {
+            id: ObjectId::new(),
+            owner_id,
+            participants: HashSet::from([owner_id]),
+            invited_players: HashSet::new(),
+            spots: Vec::new(),
+            status: GameStatus::Active,
+            date_created: DateTime::now(),
+            date_ended: None,
+        };
+
+        // game goes first, so a failed insert never leaves a dangling id in the owner's games
+        Self::get_game_collection(mongo_database)
+            .insert_one(&new_game)
+            .await?;
+
+        let owner_update = Player::get_player_collection(mongo_database)
+            .update_one(
+                doc! { "_id": owner_id },
+                doc! { "$addToSet": { "games_owned": new_game.id } },
+            )
+            .await?;
+
+        if owner_update.matched_count == 0 {
+            Self::get_game_collection(mongo_database)
+                .delete_one(doc! { "_id": new_game.id })
+                .await?;
+
+            return Err(ApiError::NotFound("game owner was not found!".into()));
+        }
+
+        Ok(new_game)
+    }
+
+    pub async fn get_game_by_id(
+        mongo_database: &Database,
+        game_id: ObjectId,
+    ) -> ApiResult<Option<Self>> {
+        let game = Self::get_game_collection(mongo_database)
+            .find_one(doc! { "_id": game_id })
+            .await?;
+
+        Ok(game)
+    }
+
+    /// Retrieve all games the player participates in, newest first
+    pub async fn get_games_for_player(
+        mongo_database: &Database,
+        player_id: ObjectId,
+    ) -> ApiResult<Vec<Self>> {
+        let options = FindOptions::builder()
+            .sort(doc! { "date_created": -1 })
+            .build();
+
+        let games = Self::get_game_collection(mongo_database)
+            .find(doc! { "participants": player_id })
+            .with_options(options)
+            .await?
+            .try_collect()
+            .await?;
+
+        Ok(games)
+    }
+
+    /// Record plate spot in an active game the spotting player participates in.
+    /// Plates that were already spotted keep their original spot.
+    /// Returns `None` if such game does not exist.
+    pub async fn add_spot(
+        mongo_database: &Database,
+        game_id: ObjectId,
+        spot: &PlateSpot,
+    ) -> ApiResult<Option<Self>> {
+        let filter = doc! {
+            "_id": game_id,
+            "participants": spot.spotted_by,
+            "status": bson::to_bson(&GameStatus::Active)?,
+            "spots.plate": { "$ne": bson::to_bson(&spot.plate)? },
+        };
+
+        let update = doc! { "$push": { "spots": bson::to_bson(spot)? } };
+
+        let spotted_game = Self::find_one_and_update(mongo_database, filter, update).await?;
+
+        if spotted_game.is_some() {
+            return Ok(spotted_game);
+        }
+
+        // plate may have been spotted already, in which case the game is returned as is
+        let game = Self::get_game_collection(mongo_database)
+            .find_one(doc! {
+                "_id": game_id,
+                "participants": spot.spotted_by,
+                "status": bson::to_bson(&GameStatus::Active)?,
+            })
+            .await?;
+
+        Ok(game)
+    }
+
+    /// Remove plate spot from an active game the player participates in.
+    /// Returns `None` if such game does not exist.
+    pub async fn remove_spot(
+        mongo_database: &Database,
+        game_id: ObjectId,
+        player_id: ObjectId,
+        plate: &SpottedPlate,
+    ) -> ApiResult<Option<Self>> {
+        let update = doc! { "$pull": { "spots": { "plate": bson::to_bson(plate)? } } };
+
+        Self::update_active_game(mongo_database, game_id, player_id, update).await
+    }
+
+    /// Unique plates spotted in this game
+    pub fn get_spotted_plates(&self) -> Vec<SpottedPlate> {
+        self.spots.iter().map(|spot| spot.plate).collect()
+    }
+
+    /// End an active game. Only game owner can end the game.
+    /// Returns `None` if such game does not exist.
+    pub async fn end_game(
+        mongo_database: &Database,
+        game_id: ObjectId,
+        owner_id: ObjectId,
+    ) -> ApiResult<Option<Self>> {
+        let filter = doc! {
+            "_id": game_id,
+            "owner_id": owner_id,
+            "status": bson::to_bson(&GameStatus::Active)?,
+        };
+
+        let update = doc! {
+            "$set": {
+                "status": bson::to_bson(&GameStatus::Ended)?,
+                "date_ended": DateTime::now(),
+            }
+        };
+
+        Self::find_one_and_update(mongo_database, filter, update).await
+    }
+
+    /// Invite player to an active game.
+    /// Invitation is recorded in both the game `invited_players` and the invitee `games_invited` sets
+    /// within a single transaction. Caller is expected to validate game ownership.
+    /// Returns `None` if active game or invitee does not exist.
+    pub async fn invite_player(
+        mongo_database: &Database,
+        game_id: ObjectId,
+        owner_id: ObjectId,
+        invitee_id: ObjectId,
+    ) -> ApiResult<Option<Self>> {
+        let filter = doc! {
+            "_id": game_id,
+            "owner_id": owner_id,
+            "status": bson::to_bson(&GameStatus::Active)?,
+            "participants": { "$ne": invitee_id },
+        };
+
+        let update = doc! { "$addToSet": { "invited_players": invitee_id } };
+
+        // transaction is aborted when the session is dropped without a commit
+        let mut session = mongo_database.client().start_session().await?;
+        session.start_transaction().await?;
+
+        let Some(invited_game) =
+            Self::find_one_and_update_in_session(mongo_database, &mut session, filter, update)
+                .await?
+        else {
+            return Ok(None);
+        };
+
+        let invitee_update = Player::get_player_collection(mongo_database)
+            .update_one(
+                doc! { "_id": invitee_id },
+                doc! { "$addToSet": { "games_invited": game_id } },
+            )
+            .session(&mut session)
+            .await?;
+
+        if invitee_update.matched_count == 0 {
+            return Ok(None);
+        }
+
+        session.commit_transaction().await?;
+
+        Ok(Some(invited_game))
+    }
+
+    /// Retrieve active games the player was invited to, newest first
+    pub async fn get_pending_invitations(
+        mongo_database: &Database,
+        player_id: ObjectId,
+    ) -> ApiResult<Vec<Self>> {
+        let options = FindOptions::builder()
+            .sort(doc! { "date_created": -1 })
+            .build();
+
+        let filter = doc! {
+            "invited_players": player_id,
+            "status": bson::to_bson(&GameStatus::Active)?,
+        };
+
+        let games = Self::get_game_collection(mongo_database)
+            .find(filter)
+            .with_options(options)
+            .await?
+            .try_collect()
+            .await?;
+
+        Ok(games)
+    }
+
+    /// Accept pending invitation, and make the invitee a game participant.
+    /// Game and invitee are updated within a single transaction.
+    /// Returns `None` if there is no pending invitation to an active game.
+    pub async fn accept_invitation(
+        mongo_database: &Database,
+        game_id: ObjectId,
+        invitee_id: ObjectId,
+    ) -> ApiResult<Option<Self>> {
+        let filter = doc! {
+            "_id": game_id,
+            "invited_players": invitee_id,
+            "status": bson::to_bson(&GameStatus::Active)?,
+        };
+
+        let update = doc! {
+            "$pull": { "invited_players": invitee_id },
+            "$addToSet": { "participants": invitee_id },
+        };
+
+        let player_update = doc! {
+            "$pull": { "games_invited": game_id },
+            "$addToSet": { "games_joined": game_id },
+        };
+
+        Self::update_invitation(mongo_database, filter, update, invitee_id, player_update).await
+    }
+
+    /// Decline pending invitation. Game and invitee are updated within a single transaction.
+    /// Returns `None` if there is no pending invitation.
+    pub async fn decline_invitation(
+        mongo_database: &Database,
+        game_id: ObjectId,
+        invitee_id: ObjectId,
+    ) -> ApiResult<Option<Self>> {
+        let filter = doc! {
+            "_id": game_id,
+            "invited_players": invitee_id,
+        };
+
+        let update = doc! { "$pull": { "invited_players": invitee_id } };
+
+        let player_update = doc! { "$pull": { "games_invited": game_id } };
+
+        Self::update_invitation(mongo_database, filter, update, invitee_id, player_update).await
+    }
+
+    /// Move games ownership, participation, invitations and spots from one player to another.
+    /// Used when merging player accounts, so it must run within the merge transaction.
+    pub async fn reassign_player(
+        mongo_database: &Database,
+        session: &mut ClientSession,
+        from_player_id: ObjectId,
+        to_player_id: ObjectId,
+    ) -> ApiResult<()> {
+        let games = Self::get_game_collection(mongo_database);
+
+        games
+            .update_many(
+                doc! { "owner_id": from_player_id },
+                doc! { "$set": { "owner_id": to_player_id } },
+            )
+            .session(&mut *session)
+            .await?;
+
+        // same array can't be both added to and pulled from in a single update
+        games
+            .update_many(
+                doc! { "participants": from_player_id },
+                doc! {
+                    "$addToSet": { "participants": to_player_id },
+                    "$pull": { "invited_players": to_player_id },
+                },
+            )
+            .session(&mut *session)
+            .await?;
+
+        games
+            .update_many(
+                doc! { "invited_players": from_player_id, "participants": { "$ne": to_player_id } },
+                doc! { "$addToSet": { "invited_players": to_player_id } },
+            )
+            .session(&mut *session)
+            .await?;
+
+        games
+            .update_many(
+                doc! { "$or": [
+                    { "participants": from_player_id },
+                    { "invited_players": from_player_id },
+                ] },
+                doc! { "$pull": {
+                    "participants": from_player_id,
+                    "invited_players": from_player_id,
+                } },
+            )
+            .session(&mut *session)
+            .await?;
+
+        games
+            .update_many(
+                doc! { "spots.spotted_by": from_player_id },
+                doc! { "$set": { "spots.$[spot].spotted_by": to_player_id } },
+            )
+            .array_filters(vec![doc! { "spot.spotted_by": from_player_id }])
+            .session(&mut *session)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Remove players from all games. Used when purging players, so it must run within the purge transaction.
+    /// Owned games other players joined are handed over to one of them, so their spots are kept.
+    /// Owned games nobody else joined are deleted, and pulled from the players invited to them.
+    pub async fn remove_players(
+        mongo_database: &Database,
+        session: &mut ClientSession,
+        player_ids: &[ObjectId],
+    ) -> ApiResult<()> {
+        let games = Self::get_game_collection(mongo_database);
+        let players = Player::get_player_collection(mongo_database);
+
+        let owned_games: Vec<Game> = games
+            .find(doc! { "owner_id": { "$in": player_ids } })
+            .session(&mut *session)
+            .await?
+            .stream(&mut *session)
+            .try_collect()
+            .await?;
+
+        let mut deleted_game_ids = Vec::new();
+
+        for owned_game in owned_games {
+            // lowest id is the earliest created player, so the handover doesn't depend on set order
+            let new_owner_id = owned_game
+                .participants
+                .iter()
+                .filter(|participant_id| !player_ids.contains(participant_id))
+                .min()
+                .copied();
+
+            let Some(new_owner_id) = new_owner_id else {
+                deleted_game_ids.push(owned_game.id);
+                continue;
+            };
+
+            games
+                .update_one(
+                    doc! { "_id": owned_game.id },
+                    doc! { "$set": { "owner_id": new_owner_id } },
+                )
+                .session(&mut *session)
+                .await?;
+
+            players
+                .update_one(
+                    doc! { "_id": new_owner_id },
+                    doc! {
+                        "$addToSet": { "games_owned": owned_game.id },
+                        "$pull": { "games_joined": owned_game.id },
+                    },
+                )
+                .session(&mut *session)
+                .await?;
+        }
+
+        if !deleted_game_ids.is_empty() {
+            games
+                .delete_many(doc! { "_id": { "$in": &deleted_game_ids } })
+                .session(&mut *session)
+                .await?;
+
+            players
+                .update_many(
+                    doc! { "$or": [
+                        { "games_joined": { "$in": &deleted_game_ids } },
+                        { "games_invited": { "$in": &deleted_game_ids } },
+                    ] },
+                    doc! { "$pull": {
+                        "games_joined": { "$in": &deleted_game_ids },
+                        "games_invited": { "$in": &deleted_game_ids },
+                    } },
+                )
+                .session(&mut *session)
+                .await?;
+        }
+
+        games
+            .update_many(
+                doc! { "$or": [
+                    { "participants": { "$in": player_ids } },
+                    { "invited_players": { "$in": player_ids } },
+                ] },
+                doc! { "$pull": {
+                    "participants": { "$in": player_ids },
+                    "invited_players": { "$in": player_ids },
+                } },
+            )
+            .session(&mut *session)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Apply invitation update to the game and the invitee in a single transaction.
+    /// Invitee is only updated if the game matched the filter.
+    async fn update_invitation(
+        mongo_database: &Database,
+        filter: bson::Document,
+        update: bson::Document,
+        invitee_id: ObjectId,
+        player_update: bson::Document,
+    ) -> ApiResult<Option<Self>> {
+        let mut session = mongo_database.client().start_session().await?;
+        session.start_transaction().await?;
+
+        let updated_game =
+            Self::find_one_and_update_in_session(mongo_database, &mut session, filter, update)
+                .await?;
+
+        if updated_game.is_some() {
+            Player::get_player_collection(mongo_database)
+                .update_one(doc! { "_id": invitee_id }, player_update)
+                .session(&mut session)
+                .await?;
+        }
+
+        session.commit_transaction().await?;
+
+        Ok(updated_game)
+    }
+
+    async fn update_active_game(
+        mongo_database: &Database,
+        game_id: ObjectId,
+        player_id: ObjectId,
+        update: bson::Document,
+    ) -> ApiResult<Option<Self>> {
+        let filter = doc! {
+            "_id": game_id,
+            "participants": player_id,
+            "status": bson::to_bson(&GameStatus::Active)?,
+        };
+
+        Self::find_one_and_update(mongo_database, filter, update).await
+    }
+
+    /// Apply update to a single game and return the updated document
+    async fn find_one_and_update(
+        mongo_database: &Database,
+        filter: bson::Document,
+        update: bson::Document,
+    ) -> ApiResult<Option<Self>> {
+        let options = FindOneAndUpdateOptions::builder()
+            .return_document(ReturnDocument::After)
+            .build();
+
+        let updated_game = Self::get_game_collection(mongo_database)
+            .find_one_and_update(filter, update)
+            .with_options(options)
+            .await?;
+
+        Ok(updated_game)
+    }
+
+    /// Apply update to a single game within the session transaction and return the updated document
+    async fn find_one_and_update_in_session(
+        mongo_database: &Database,
+        session: &mut ClientSession,
+        filter: bson::Document,
+        update: bson::Document,
+    ) -> ApiResult<Option<Self>> {
+        let updated_game = Self::get_game_collection(mongo_database)
+            .find_one_and_update(filter, update)
+            .return_document(ReturnDocument::After)
+            .session(session)
+            .await?;
+
+        Ok(updated_game)
+    }
+}
@@ -1,14 +1,14 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 #[allow(dead_code)]
-#[derive(PartialEq, Eq, Hash, Debug, Deserialize)]
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy, Deserialize, Serialize)]
 pub enum Country {
     US,
     CA,
 }
 
 #[allow(dead_code)]
-#[derive(PartialEq, Eq, Hash, Debug, Deserialize)]
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy, Deserialize, Serialize)]
 pub enum StateOrProvince {
     // US
     AL,
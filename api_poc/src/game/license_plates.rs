@@ -1,20 +1,25 @@
 use super::license_plate_enums::{Country, StateOrProvince};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
 use std::sync::LazyLock;
 
-#[derive(PartialEq, Eq, Hash, Debug, Deserialize)]
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy, Deserialize, Serialize)]
 pub struct SpottedPlate {
     pub country: Country,
     pub state_or_province: StateOrProvince,
 }
 
+impl SpottedPlate {
+    /// Check if plate is a known country and state/province combination
+    pub fn is_valid_game_plate(&self) -> bool {
+        VALID_GAME_PLATES_WITH_BORDERS.contains_key(&(self.country, self.state_or_province))
+    }
+}
+
 pub type PlateWithBorder = HashMap<(Country, StateOrProvince), HashSet<(Country, StateOrProvince)>>;
 
-#[allow(dead_code)]
-static VALID_GAME_PLATES_WITH_BORDERS: LazyLock<PlateWithBorder> = LazyLock::new(|| {
-    // TODO add Canadian borders (will need milestones and score updates)
-    // TODO consider adding cross-country borders (eg. coast-to-coast through Canada)
+pub static VALID_GAME_PLATES_WITH_BORDERS: LazyLock<PlateWithBorder> = LazyLock::new(|| {
+    // cross-country borders are included so chains can go through Canada and back
     HashMap::from([
         // US
         (
@@ -26,7 +31,13 @@ static VALID_GAME_PLATES_WITH_BORDERS: LazyLock<PlateWithBorder> = LazyLock::new
                 (Country::US, StateOrProvince::MS),
             ]),
         ),
-        ((Country::US, StateOrProvince::AK), HashSet::from([])),
+        (
+            (Country::US, StateOrProvince::AK),
+            HashSet::from([
+                (Country::CA, StateOrProvince::YT),
+                (Country::CA, StateOrProvince::BC),
+            ]),
+        ),
         (
             (Country::US, StateOrProvince::AZ),
             HashSet::from([
@@ -109,6 +120,7 @@ static VALID_GAME_PLATES_WITH_BORDERS: LazyLock<PlateWithBorder> = LazyLock::new
                 (Country::US, StateOrProvince::UT),
                 (Country::US, StateOrProvince::WY),
                 (Country::US, StateOrProvince::MT),
+                (Country::CA, StateOrProvince::BC),
             ]),
         ),
         (
@@ -172,7 +184,11 @@ static VALID_GAME_PLATES_WITH_BORDERS: LazyLock<PlateWithBorder> = LazyLock::new
         ),
         (
             (Country::US, StateOrProvince::ME),
-            HashSet::from([(Country::US, StateOrProvince::NH)]),
+            HashSet::from([
+                (Country::US, StateOrProvince::NH),
+                (Country::CA, StateOrProvince::QC),
+                (Country::CA, StateOrProvince::NB),
+            ]),
         ),
         (
             (Country::US, StateOrProvince::MD),
@@ -199,6 +215,7 @@ static VALID_GAME_PLATES_WITH_BORDERS: LazyLock<PlateWithBorder> = LazyLock::new
                 (Country::US, StateOrProvince::WI),
                 (Country::US, StateOrProvince::IN),
                 (Country::US, StateOrProvince::OH),
+                (Country::CA, StateOrProvince::ON),
             ]),
         ),
         (
@@ -208,6 +225,8 @@ static VALID_GAME_PLATES_WITH_BORDERS: LazyLock<PlateWithBorder> = LazyLock::new
                 (Country::US, StateOrProvince::SD),
                 (Country::US, StateOrProvince::IA),
                 (Country::US, StateOrProvince::WI),
+                (Country::CA, StateOrProvince::MB),
+                (Country::CA, StateOrProvince::ON),
             ]),
         ),
         (
@@ -239,6 +258,9 @@ static VALID_GAME_PLATES_WITH_BORDERS: LazyLock<PlateWithBorder> = LazyLock::new
                 (Country::US, StateOrProvince::WY),
                 (Country::US, StateOrProvince::SD),
                 (Country::US, StateOrProvince::ND),
+                (Country::CA, StateOrProvince::BC),
+                (Country::CA, StateOrProvince::AB),
+                (Country::CA, StateOrProvince::SK),
             ]),
         ),
         (
@@ -268,6 +290,7 @@ static VALID_GAME_PLATES_WITH_BORDERS: LazyLock<PlateWithBorder> = LazyLock::new
                 (Country::US, StateOrProvince::VT),
                 (Country::US, StateOrProvince::MA),
                 (Country::US, StateOrProvince::ME),
+                (Country::CA, StateOrProvince::QC),
             ]),
         ),
         (
@@ -295,6 +318,8 @@ static VALID_GAME_PLATES_WITH_BORDERS: LazyLock<PlateWithBorder> = LazyLock::new
                 (Country::US, StateOrProvince::CT),
                 (Country::US, StateOrProvince::MA),
                 (Country::US, StateOrProvince::VT),
+                (Country::CA, StateOrProvince::ON),
+                (Country::CA, StateOrProvince::QC),
             ]),
         ),
         (
@@ -312,6 +337,8 @@ static VALID_GAME_PLATES_WITH_BORDERS: LazyLock<PlateWithBorder> = LazyLock::new
                 (Country::US, StateOrProvince::MT),
                 (Country::US, StateOrProvince::SD),
                 (Country::US, StateOrProvince::MN),
+                (Country::CA, StateOrProvince::SK),
+                (Country::CA, StateOrProvince::MB),
             ]),
         ),
         (
@@ -418,6 +445,7 @@ static VALID_GAME_PLATES_WITH_BORDERS: LazyLock<PlateWithBorder> = LazyLock::new
                 (Country::US, StateOrProvince::NY),
                 (Country::US, StateOrProvince::MA),
                 (Country::US, StateOrProvince::NH),
+                (Country::CA, StateOrProvince::QC),
             ]),
         ),
         (
@@ -435,6 +463,7 @@ static VALID_GAME_PLATES_WITH_BORDERS: LazyLock<PlateWithBorder> = LazyLock::new
             HashSet::from([
                 (Country::US, StateOrProvince::OR),
                 (Country::US, StateOrProvince::ID),
+                (Country::CA, StateOrProvince::BC),
             ]),
         ),
         (
@@ -467,20 +496,119 @@ static VALID_GAME_PLATES_WITH_BORDERS: LazyLock<PlateWithBorder> = LazyLock::new
                 (Country::US, StateOrProvince::UT),
             ]),
         ),
-        // CA
-        ((Country::CA, StateOrProvince::AB), HashSet::from([])),
-        ((Country::CA, StateOrProvince::BC), HashSet::from([])),
-        ((Country::CA, StateOrProvince::MB), HashSet::from([])),
-        ((Country::CA, StateOrProvince::NB), HashSet::from([])),
-        ((Country::CA, StateOrProvince::NL), HashSet::from([])),
-        ((Country::CA, StateOrProvince::NT), HashSet::from([])),
-        ((Country::CA, StateOrProvince::NS), HashSet::from([])),
-        ((Country::CA, StateOrProvince::NU), HashSet::from([])),
-        ((Country::CA, StateOrProvince::ON), HashSet::from([])),
-        ((Country::CA, StateOrProvince::PE), HashSet::from([])),
-        ((Country::CA, StateOrProvince::QC), HashSet::from([])),
-        ((Country::CA, StateOrProvince::SK), HashSet::from([])),
-        ((Country::CA, StateOrProvince::YT), HashSet::from([])),
+        // Canada
+        (
+            (Country::CA, StateOrProvince::AB),
+            HashSet::from([
+                (Country::CA, StateOrProvince::BC),
+                (Country::CA, StateOrProvince::NT),
+                (Country::CA, StateOrProvince::SK),
+                (Country::US, StateOrProvince::MT),
+            ]),
+        ),
+        (
+            (Country::CA, StateOrProvince::BC),
+            HashSet::from([
+                (Country::CA, StateOrProvince::YT),
+                (Country::CA, StateOrProvince::NT),
+                (Country::CA, StateOrProvince::AB),
+                (Country::US, StateOrProvince::WA),
+                (Country::US, StateOrProvince::ID),
+                (Country::US, StateOrProvince::MT),
+                (Country::US, StateOrProvince::AK),
+            ]),
+        ),
+        (
+            (Country::CA, StateOrProvince::MB),
+            HashSet::from([
+                (Country::CA, StateOrProvince::SK),
+                (Country::CA, StateOrProvince::NU),
+                (Country::CA, StateOrProvince::ON),
+                (Country::US, StateOrProvince::ND),
+                (Country::US, StateOrProvince::MN),
+            ]),
+        ),
+        (
+            (Country::CA, StateOrProvince::NB),
+            HashSet::from([
+                (Country::CA, StateOrProvince::QC),
+                (Country::CA, StateOrProvince::NS),
+                (Country::CA, StateOrProvince::PE),
+                (Country::US, StateOrProvince::ME),
+            ]),
+        ),
+        (
+            (Country::CA, StateOrProvince::NL),
+            HashSet::from([(Country::CA, StateOrProvince::QC)]),
+        ),
+        (
+            (Country::CA, StateOrProvince::NT),
+            HashSet::from([
+                (Country::CA, StateOrProvince::YT),
+                (Country::CA, StateOrProvince::BC),
+                (Country::CA, StateOrProvince::AB),
+                (Country::CA, StateOrProvince::SK),
+                (Country::CA, StateOrProvince::NU),
+            ]),
+        ),
+        (
+            (Country::CA, StateOrProvince::NS),
+            HashSet::from([(Country::CA, StateOrProvince::NB)]),
+        ),
+        (
+            (Country::CA, StateOrProvince::NU),
+            HashSet::from([
+                (Country::CA, StateOrProvince::NT),
+                (Country::CA, StateOrProvince::MB),
+            ]),
+        ),
+        (
+            (Country::CA, StateOrProvince::ON),
+            HashSet::from([
+                (Country::CA, StateOrProvince::MB),
+                (Country::CA, StateOrProvince::QC),
+                (Country::US, StateOrProvince::MN),
+                (Country::US, StateOrProvince::MI),
+                (Country::US, StateOrProvince::NY),
+            ]),
+        ),
+        (
+            (Country::CA, StateOrProvince::PE),
+            HashSet::from([
+                // connected through Confederation Bridge
+                (Country::CA, StateOrProvince::NB),
+            ]),
+        ),
+        (
+            (Country::CA, StateOrProvince::QC),
+            HashSet::from([
+                (Country::CA, StateOrProvince::ON),
+                (Country::CA, StateOrProvince::NL),
+                (Country::CA, StateOrProvince::NB),
+                (Country::US, StateOrProvince::NY),
+                (Country::US, StateOrProvince::VT),
+                (Country::US, StateOrProvince::NH),
+                (Country::US, StateOrProvince::ME),
+            ]),
+        ),
+        (
+            (Country::CA, StateOrProvince::SK),
+            HashSet::from([
+                (Country::CA, StateOrProvince::AB),
+                (Country::CA, StateOrProvince::NT),
+                (Country::CA, StateOrProvince::MB),
+                (Country::US, StateOrProvince::MT),
+                (Country::US, StateOrProvince::ND),
+            ]),
+        ),
+        (
+            (Country::CA, StateOrProvince::YT),
+            HashSet::from([
+                (Country::CA, StateOrProvince::NT),
+                (Country::CA, StateOrProvince::BC),
+                (Country::US, StateOrProvince::AK),
+            ]),
+        ),
     ])
 });
 
@@ -525,4 +653,66 @@ mod tests {
 
         assert_eq!(expected_borders, *actual_borders);
     }
+
+    #[test]
+    fn will_validate_spotted_plate() {
+        let valid_plate = SpottedPlate {
+            country: Country::CA,
+            state_or_province: StateOrProvince::BC,
+        };
+        let invalid_plate = SpottedPlate {
+            country: Country::US,
+            state_or_province: StateOrProvince::BC,
+        };
+
+        assert!(valid_plate.is_valid_game_plate());
+        assert!(!invalid_plate.is_valid_game_plate());
+    }
+
+    #[test]
+    fn will_have_symmetric_borders() {
+        for (plate, borders) in VALID_GAME_PLATES_WITH_BORDERS.iter() {
+            for border in borders {
+                let reverse_borders = VALID_GAME_PLATES_WITH_BORDERS
+                    .get(border)
+                    .unwrap_or_else(|| panic!("Expected {border:?} to be a valid plate"));
+
+                assert!(
+                    reverse_borders.contains(plate),
+                    "Expected {border:?} to border {plate:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn will_retrieve_correct_ca_bc_borders() {
+        let expected_borders = HashSet::from([
+            (Country::CA, StateOrProvince::YT),
+            (Country::CA, StateOrProvince::NT),
+            (Country::CA, StateOrProvince::AB),
+            (Country::US, StateOrProvince::WA),
+            (Country::US, StateOrProvince::ID),
+            (Country::US, StateOrProvince::MT),
+            (Country::US, StateOrProvince::AK),
+        ]);
+
+        let key = (Country::CA, StateOrProvince::BC);
+
+        let actual_borders = VALID_GAME_PLATES_WITH_BORDERS
+            .get(&key)
+            .expect("Expected CA-BC to be found");
+
+        assert_eq!(expected_borders, *actual_borders);
+    }
+
+    #[test]
+    fn will_border_every_canadian_plate() {
+        let actual_isolated_plates: Vec<_> = VALID_GAME_PLATES_WITH_BORDERS
+            .iter()
+            .filter(|((country, _), borders)| *country == Country::CA && borders.is_empty())
+            .collect();
+
+        assert!(actual_isolated_plates.is_empty());
+    }
 }
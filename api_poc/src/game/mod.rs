@@ -4,4 +4,13 @@ pub mod license_plate_enums;
 
 pub mod score_calculator;
 
+pub mod achievements;
+
+pub mod achievement_definitions;
+
+pub mod plate_spot;
+
 pub mod player;
+
+#[allow(clippy::module_inception)]
+pub mod game;
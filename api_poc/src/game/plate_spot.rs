@@ -0,0 +1,89 @@
+use bson::{oid::ObjectId, DateTime};
+use serde::{Deserialize, Serialize};
+
+use super::license_plates::SpottedPlate;
+
+/// Approximate location where the plate was spotted
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+pub struct SpotLocation {
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+#[allow(dead_code)]
+impl SpotLocation {
+    /// Check if coordinates are within valid latitude and longitude ranges
+    pub fn is_valid(&self) -> bool {
+        (-90.0..=90.0).contains(&self.latitude) && (-180.0..=180.0).contains(&self.longitude)
+    }
+}
+
+/// Single plate spot within a game.
+/// Game keeps at most one spot per plate, so the first player to spot a plate gets the credit.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct PlateSpot {
+    pub plate: SpottedPlate,
+    /// Player that spotted the plate
+    pub spotted_by: ObjectId,
+    /// Server time when the spot was recorded
+    pub date_spotted: DateTime,
+    /// Device time when the plate was spotted. Spots may be recorded offline and synced later
+    pub client_date_spotted: Option<DateTime>,
+    pub location: Option<SpotLocation>,
+}
+
+#[allow(dead_code)]
+impl PlateSpot {
+    /// Create new spot recorded at the current server time
+    pub fn new(
+        plate: SpottedPlate,
+        spotted_by: ObjectId,
+        client_date_spotted: Option<DateTime>,
+        location: Option<SpotLocation>,
+    ) -> Self {
+        Self {
+            plate,
+            spotted_by,
+            date_spotted: DateTime::now(),
+            client_date_spotted,
+            location,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn will_validate_spot_location() {
+        let valid_locations = [
+            SpotLocation {
+                latitude: 47.6062,
+                longitude: -122.3321,
+            },
+            SpotLocation {
+                latitude: -90.0,
+                longitude: 180.0,
+            },
+        ];
+
+        let invalid_locations = [
+            SpotLocation {
+                latitude: 90.1,
+                longitude: 0.0,
+            },
+            SpotLocation {
+                latitude: 0.0,
+                longitude: -180.1,
+            },
+            SpotLocation {
+                latitude: f64::NAN,
+                longitude: 0.0,
+            },
+        ];
+
+        assert!(valid_locations.iter().all(SpotLocation::is_valid));
+        assert!(!invalid_locations.iter().any(SpotLocation::is_valid));
+    }
+}
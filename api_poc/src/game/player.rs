@@ -1,10 +1,34 @@
-use std::{collections::HashSet, error::Error};
+use std::collections::HashSet;
 
 use bson::{doc, oid::ObjectId};
 use futures_util::TryStreamExt;
-use mongodb::{bson::DateTime, options::IndexOptions, Collection, Database, IndexModel};
+use mongodb::{
+    bson::DateTime,
+    options::{FindOneAndUpdateOptions, IndexOptions, ReturnDocument},
+    Collection, Database, IndexModel,
+};
+
+use super::game::Game;
 use serde::{self, Deserialize, Serialize};
 
+use crate::api_error::{ApiError, ApiResult};
+
+/// Mongo generated name of the unique provider identity index
+pub const IDENTITY_INDEX_NAME: &str = "provider_name_1_provider_identity_id_1";
+
+/// Synthetic provider of guest players signed in with a device secret
+pub const GUEST_PROVIDER_NAME: &str = "guest";
+
+/// Rotated refresh token hashes kept for reuse detection. Reuse of older tokens is rejected as invalid
+pub const MAX_USED_REFRESH_TOKENS: i32 = 100;
+
+/// Additional provider identity that signs in as the same player
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct LinkedIdentity {
+    pub provider_name: String,
+    pub provider_identity_id: String,
+}
+
 #[allow(dead_code)]
 #[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
 pub struct Player {
@@ -20,15 +44,42 @@ pub struct Player {
     #[serde(default)]
     pub games_invited: HashSet<ObjectId>,
 
+    /// Games joined by accepting an invitation
+    #[serde(default)]
+    pub games_joined: HashSet<ObjectId>,
+
     /// OAuth provder - Google, Apple, etc
-    /// For the time being, we wont be opening a can of worms called merged accounts, so for now unique login == new player
+    /// Identity the player was created with. Other identities can be linked later
     pub provider_name: String,
     /// Identity unique id (in the context of Provider above)
     pub provider_identity_id: String,
-    /// Game API refresh token
+    /// Identities linked to this player, or taken over from merged players
+    #[serde(default)]
+    pub linked_identities: Vec<LinkedIdentity>,
+    /// SHA-256 hash of the current Game API refresh token. Empty when player has no active refresh token
     pub api_refresh_token: String,
     /// Game API refresh token expiration
     pub api_refresh_token_exp: DateTime,
+    /// Hashes of the last `MAX_USED_REFRESH_TOKENS` rotated refresh tokens from the current token family.
+    /// Presenting any of these again means the token was stolen, and the whole family is revoked
+    #[serde(default)]
+    pub used_refresh_tokens: HashSet<String>,
+    /// Player roles, e.g. `admin`. Included in api tokens issued for the player
+    #[serde(default)]
+    pub roles: HashSet<String>,
+    /// Last sign in or token refresh. Inactive guest players are purged
+    #[serde(default)]
+    pub date_last_active: Option<DateTime>,
+}
+
+/// Result of the refresh token rotation
+#[derive(Debug, PartialEq)]
+pub enum RefreshTokenRotation {
+    Rotated,
+    /// Already used token was presented. Token family was revoked
+    Reused,
+    /// Token is unknown or expired
+    Invalid,
 }
 
 #[allow(dead_code)]
@@ -52,15 +103,63 @@ impl Player {
             .await
     }
 
-    /// Retrieve existing player using identity
+    /// Linked identity can belong to a single player only.
+    /// Uniqueness between primary and linked identities is checked when linking.
+    pub async fn create_linked_identity_index(
+        mongo_database: &Database,
+    ) -> Result<mongodb::results::CreateIndexResult, mongodb::error::Error> {
+        // players without linked identities must not collide on a missing key
+        let options = IndexOptions::builder()
+            .unique(true)
+            .partial_filter_expression(
+                doc! { "linked_identities.provider_identity_id": { "$exists": true } },
+            )
+            .build();
+        let model = IndexModel::builder()
+            .keys(doc! {
+                "linked_identities.provider_name": 1,
+                "linked_identities.provider_identity_id": 1,
+            })
+            .options(options)
+            .build();
+
+        Self::get_player_collection(mongo_database)
+            .create_index(model)
+            .await
+    }
+
+    /// Check if unique provider identity index was created
+    pub async fn has_identity_index(mongo_database: &Database) -> ApiResult<bool> {
+        let index_names = Self::get_player_collection(mongo_database)
+            .list_index_names()
+            .await?;
+
+        Ok(index_names.iter().any(|name| name == IDENTITY_INDEX_NAME))
+    }
+
+    pub async fn get_player_by_id(
+        mongo_database: &Database,
+        player_id: ObjectId,
+    ) -> ApiResult<Option<Player>> {
+        let player = Self::get_player_collection(mongo_database)
+            .find_one(doc! { "_id": player_id })
+            .await?;
+
+        Ok(player)
+    }
+
+    /// Retrieve existing player using primary or linked identity
     pub async fn get_player_by_existing_identity(
         mongo_database: &Database,
         provider_name: &str,
         provider_identity_id: &str,
-        // using Box dyn because we may get back mongo error or "business" error - duplicate player
-    ) -> Result<Option<Player>, Box<dyn Error>> {
-        let player_filter =
-            doc! { "provider_name": provider_name, "provider_identity_id": provider_identity_id };
+    ) -> ApiResult<Option<Player>> {
+        let player_filter = doc! { "$or": [
+            { "provider_name": provider_name, "provider_identity_id": provider_identity_id },
+            { "linked_identities": {
+                "$elemMatch": { "provider_name": provider_name, "provider_identity_id": provider_identity_id }
+            } },
+        ] };
 
         let mut cursor = Self::get_player_collection(mongo_database)
             .find(player_filter)
@@ -69,7 +168,9 @@ impl Player {
         let first_player = cursor.try_next().await?;
 
         if cursor.try_next().await?.is_some() {
-            return Err("more than one matching player found!".into());
+            return Err(ApiError::Internal(format!(
+                "more than one player found for {provider_name} identity {provider_identity_id}!"
+            )));
         }
 
         Ok(first_player)
@@ -82,7 +183,7 @@ impl Player {
         provider_identity_id: &str,
         api_refresh_token: &str,
         api_refresh_token_exp: DateTime,
-    ) -> Result<Self, Box<dyn Error>> {
+    ) -> ApiResult<Self> {
         // validate for existing players
         let existing_player = Self::get_player_by_existing_identity(
             mongo_database,
@@ -92,7 +193,9 @@ impl Player {
         .await?;
 
         if existing_player.is_some() {
-            return Err("player with supplied identity already exist!".into());
+            return Err(ApiError::Conflict(
+                "player with supplied identity already exist!".into(),
+            ));
         }
 
         let new_player = Self {
@@ -107,6 +210,11 @@ impl Player {
 
             games_owned: HashSet::new(),
             games_invited: HashSet::new(),
+            games_joined: HashSet::new(),
+            used_refresh_tokens: HashSet::new(),
+            roles: HashSet::new(),
+            linked_identities: Vec::new(),
+            date_last_active: Some(DateTime::now()),
         };
 
         Self::get_player_collection(mongo_database)
@@ -115,4 +223,431 @@ impl Player {
 
         Ok(new_player)
     }
+
+    /// Start new refresh token family. Previous refresh tokens can no longer be used
+    pub async fn start_refresh_token_family(
+        mongo_database: &Database,
+        player_id: ObjectId,
+        refresh_token_hash: &str,
+        refresh_token_exp: DateTime,
+    ) -> ApiResult<()> {
+        let update_result = Self::get_player_collection(mongo_database)
+            .update_one(
+                doc! { "_id": player_id },
+                doc! {
+                    "$set": {
+                        "api_refresh_token": refresh_token_hash,
+                        "api_refresh_token_exp": refresh_token_exp,
+                        "used_refresh_tokens": [],
+                        "date_last_active": DateTime::now(),
+                    }
+                },
+            )
+            .await?;
+
+        if update_result.matched_count == 0 {
+            return Err(ApiError::NotFound(format!(
+                "player {player_id} was not found!"
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Invalidate current refresh token, e.g. on logout.
+    /// Used token hashes are kept, so reuse of a token stolen before the logout is still detected.
+    /// Player activity is not updated, since revoking the token is not a sign in.
+    pub async fn revoke_refresh_token(
+        mongo_database: &Database,
+        player_id: ObjectId,
+    ) -> ApiResult<()> {
+        // empty hash never matches a presented refresh token
+        Self::get_player_collection(mongo_database)
+            .update_one(
+                doc! { "_id": player_id },
+                doc! {
+                    "$set": {
+                        "api_refresh_token": "",
+                        "api_refresh_token_exp": DateTime::now(),
+                    }
+                },
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    /// Replace current refresh token with a new one.
+    /// Rotated token is remembered so its reuse can be detected.
+    pub async fn rotate_refresh_token(
+        mongo_database: &Database,
+        player_id: ObjectId,
+        presented_token_hash: &str,
+        new_token_hash: &str,
+        new_token_exp: DateTime,
+    ) -> ApiResult<RefreshTokenRotation> {
+        let players = Self::get_player_collection(mongo_database);
+
+        let rotate_result = players
+            .update_one(
+                doc! {
+                    "_id": player_id,
+                    "api_refresh_token": presented_token_hash,
+                    "api_refresh_token_exp": { "$gt": DateTime::now() },
+                },
+                doc! {
+                    "$set": {
+                        "api_refresh_token": new_token_hash,
+                        "api_refresh_token_exp": new_token_exp,
+                        "date_last_active": DateTime::now(),
+                    },
+                    // only the most recent hashes are kept, so long-lived families don't grow the document
+                    "$push": { "used_refresh_tokens": {
+                        "$each": [presented_token_hash],
+                        "$slice": -MAX_USED_REFRESH_TOKENS,
+                    } },
+                },
+            )
+            .await?;
+
+        if rotate_result.modified_count == 1 {
+            return Ok(RefreshTokenRotation::Rotated);
+        }
+
+        // revoke the whole family so neither the attacker nor the victim can keep refreshing
+        let revoke_result = players
+            .update_one(
+                doc! {
+                    "_id": player_id,
+                    "used_refresh_tokens": presented_token_hash,
+                },
+                doc! {
+                    "$set": {
+                        "api_refresh_token": "",
+                        "used_refresh_tokens": [],
+                    }
+                },
+            )
+            .await?;
+
+        if revoke_result.matched_count == 1 {
+            return Ok(RefreshTokenRotation::Reused);
+        }
+
+        Ok(RefreshTokenRotation::Invalid)
+    }
+
+    /// Replace player roles. Roles are applied to api tokens issued after the next refresh
+    pub async fn set_roles(
+        mongo_database: &Database,
+        player_id: ObjectId,
+        roles: &HashSet<String>,
+    ) -> ApiResult<()> {
+        let update_result = Self::get_player_collection(mongo_database)
+            .update_one(
+                doc! { "_id": player_id },
+                doc! { "$set": { "roles": roles.iter().collect::<Vec<_>>() } },
+            )
+            .await?;
+
+        if update_result.matched_count == 0 {
+            return Err(ApiError::NotFound(format!(
+                "player {player_id} was not found!"
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Link additional provider identity to the player.
+    /// Linking identity that already belongs to the player is a no-op.
+    /// Guest identities can't be linked, since guest device secret must not become a credential of another player.
+    pub async fn link_identity(
+        mongo_database: &Database,
+        player_id: ObjectId,
+        provider_name: &str,
+        provider_identity_id: &str,
+    ) -> ApiResult<()> {
+        if provider_name == GUEST_PROVIDER_NAME {
+            return Err(ApiError::Validation(
+                "guest identity can't be linked to a player!".into(),
+            ));
+        }
+
+        let identity_owner = Self::get_player_by_existing_identity(
+            mongo_database,
+            provider_name,
+            provider_identity_id,
+        )
+        .await?;
+
+        match identity_owner {
+            Some(identity_owner) if identity_owner.id == player_id => return Ok(()),
+            Some(_) => {
+                return Err(ApiError::Conflict(
+                    "identity belongs to another player! Players must be merged instead.".into(),
+                ))
+            }
+            None => {}
+        }
+
+        let linked_identity = LinkedIdentity {
+            provider_name: provider_name.into(),
+            provider_identity_id: provider_identity_id.into(),
+        };
+
+        let update_result = Self::get_player_collection(mongo_database)
+            .update_one(
+                doc! { "_id": player_id },
+                doc! { "$addToSet": { "linked_identities": bson::to_bson(&linked_identity)? } },
+            )
+            .await?;
+
+        if update_result.matched_count == 0 {
+            return Err(ApiError::NotFound(format!(
+                "player {player_id} was not found!"
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Merge source player into target player, and delete the source player.
+    /// Target takes over source identities, games, invitations and spots within a single transaction,
+    /// so the source identities keep signing in as the merged player. Target roles are kept as is.
+    /// Requires mongo replica set.
+    pub async fn merge_players(
+        mongo_database: &Database,
+        target_player_id: ObjectId,
+        source_player_id: ObjectId,
+    ) -> ApiResult<Player> {
+        if target_player_id == source_player_id {
+            return Err(ApiError::Validation(
+                "player can't be merged with itself!".into(),
+            ));
+        }
+
+        let players = Self::get_player_collection(mongo_database);
+
+        let mut session = mongo_database.client().start_session().await?;
+        session.start_transaction().await?;
+
+        let target_player = players
+            .find_one(doc! { "_id": target_player_id })
+            .session(&mut session)
+            .await?
+            .ok_or_else(|| {
+                ApiError::NotFound(format!("player {target_player_id} was not found!"))
+            })?;
+
+        let source_player = players
+            .find_one(doc! { "_id": source_player_id })
+            .session(&mut session)
+            .await?
+            .ok_or_else(|| {
+                ApiError::NotFound(format!("player {source_player_id} was not found!"))
+            })?;
+
+        let merged_player = target_player.merged_with(source_player);
+
+        // source must be gone before its identities are linked to the target to satisfy unique indexes
+        players
+            .delete_one(doc! { "_id": source_player_id })
+            .session(&mut session)
+            .await?;
+
+        players
+            .replace_one(doc! { "_id": target_player_id }, &merged_player)
+            .session(&mut session)
+            .await?;
+
+        Game::reassign_player(
+            mongo_database,
+            &mut session,
+            source_player_id,
+            target_player_id,
+        )
+        .await?;
+
+        session.commit_transaction().await?;
+
+        Ok(merged_player)
+    }
+
+    pub fn is_guest(&self) -> bool {
+        self.provider_name == GUEST_PROVIDER_NAME
+    }
+
+    /// Replace guest identity with a full provider identity. Guest games are kept as is.
+    /// Returns `None` if the player doesn't exist or is not a guest.
+    pub async fn upgrade_guest(
+        mongo_database: &Database,
+        player_id: ObjectId,
+        name: &str,
+        provider_name: &str,
+        provider_identity_id: &str,
+    ) -> ApiResult<Option<Player>> {
+        let options = FindOneAndUpdateOptions::builder()
+            .return_document(ReturnDocument::After)
+            .build();
+
+        let upgraded_player = Self::get_player_collection(mongo_database)
+            .find_one_and_update(
+                doc! { "_id": player_id, "provider_name": GUEST_PROVIDER_NAME },
+                doc! { "$set": {
+                    "name": name,
+                    "provider_name": provider_name,
+                    "provider_identity_id": provider_identity_id,
+                } },
+            )
+            .with_options(options)
+            .await?;
+
+        Ok(upgraded_player)
+    }
+
+    /// Delete guest players inactive since the supplied date, and remove them from their games.
+    /// Returns ids of purged players, so their tokens can be revoked.
+    pub async fn purge_inactive_guests(
+        mongo_database: &Database,
+        inactive_since: DateTime,
+    ) -> ApiResult<Vec<ObjectId>> {
+        let players = Self::get_player_collection(mongo_database);
+
+        let inactive_guest_filter = doc! {
+            "provider_name": GUEST_PROVIDER_NAME,
+            "$or": [
+                { "date_last_active": { "$lt": inactive_since } },
+                { "date_last_active": null, "date_created": { "$lt": inactive_since } },
+            ],
+        };
+
+        let mut session = mongo_database.client().start_session().await?;
+        session.start_transaction().await?;
+
+        let guest_ids: Vec<ObjectId> = players
+            .find(inactive_guest_filter.clone())
+            .session(&mut session)
+            .await?
+            .stream(&mut session)
+            .map_ok(|guest| guest.id)
+            .try_collect()
+            .await?;
+
+        if guest_ids.is_empty() {
+            return Ok(guest_ids);
+        }
+
+        // filter is repeated, so a guest that became active or was upgraded since the find is not deleted
+        let mut delete_filter = inactive_guest_filter;
+        delete_filter.insert("_id", doc! { "$in": &guest_ids });
+
+        let delete_result = players
+            .delete_many(delete_filter)
+            .session(&mut session)
+            .await?;
+
+        // dropping the session aborts the transaction, and the purge is retried on the next run
+        if delete_result.deleted_count != guest_ids.len() as u64 {
+            return Ok(Vec::new());
+        }
+
+        Game::remove_players(mongo_database, &mut session, &guest_ids).await?;
+
+        session.commit_transaction().await?;
+
+        Ok(guest_ids)
+    }
+
+    fn merged_with(mut self, source: Player) -> Player {
+        self.games_owned.extend(source.games_owned);
+
+        self.games_joined.extend(source.games_joined);
+        self.games_joined
+            .retain(|game_id| !self.games_owned.contains(game_id));
+
+        self.games_invited.extend(source.games_invited);
+        self.games_invited.retain(|game_id| {
+            !self.games_owned.contains(game_id) && !self.games_joined.contains(game_id)
+        });
+
+        // guest device secret must not become a credential of the merged player
+        let source_identities = std::iter::once(LinkedIdentity {
+            provider_name: source.provider_name,
+            provider_identity_id: source.provider_identity_id,
+        })
+        .chain(source.linked_identities)
+        .filter(|identity| identity.provider_name != GUEST_PROVIDER_NAME);
+
+        for source_identity in source_identities {
+            if !self.linked_identities.contains(&source_identity) {
+                self.linked_identities.push(source_identity);
+            }
+        }
+
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_test_player(provider_identity_id: &str) -> Player {
+        Player {
+            id: ObjectId::new(),
+            name: "test player".into(),
+            date_created: DateTime::now(),
+            games_owned: HashSet::new(),
+            games_invited: HashSet::new(),
+            games_joined: HashSet::new(),
+            provider_name: "test_provider".into(),
+            provider_identity_id: provider_identity_id.into(),
+            api_refresh_token: String::new(),
+            api_refresh_token_exp: DateTime::now(),
+            used_refresh_tokens: HashSet::new(),
+            roles: HashSet::new(),
+            linked_identities: Vec::new(),
+            date_last_active: None,
+        }
+    }
+
+    #[test]
+    fn will_merge_games_and_identities() {
+        let shared_game = ObjectId::new();
+        let source_game = ObjectId::new();
+
+        let mut target = create_test_player("target_identity");
+        target.games_owned.insert(shared_game);
+
+        let mut source = create_test_player("source_identity");
+        source.games_joined.insert(shared_game);
+        source.games_invited.insert(source_game);
+
+        let actual_player = target.clone().merged_with(source);
+
+        assert_eq!(target.id, actual_player.id);
+        assert_eq!(HashSet::from([shared_game]), actual_player.games_owned);
+        assert!(actual_player.games_joined.is_empty());
+        assert_eq!(HashSet::from([source_game]), actual_player.games_invited);
+        assert_eq!(
+            vec![LinkedIdentity {
+                provider_name: "test_provider".into(),
+                provider_identity_id: "source_identity".into(),
+            }],
+            actual_player.linked_identities
+        );
+    }
+
+    #[test]
+    fn will_not_merge_guest_identity() {
+        let target = create_test_player("target_identity");
+
+        let mut source = create_test_player("guest_identity");
+        source.provider_name = GUEST_PROVIDER_NAME.into();
+
+        let actual_player = target.merged_with(source);
+
+        assert!(actual_player.linked_identities.is_empty());
+    }
 }
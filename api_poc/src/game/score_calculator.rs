@@ -1,9 +1,8 @@
 use std::collections::HashSet;
-use std::sync::LazyLock;
 
 use serde::Serialize;
 
-use super::license_plate_enums::{Country, StateOrProvince};
+use super::achievements::AchievementRegistry;
 use super::license_plates::SpottedPlate;
 
 #[derive(Serialize)]
@@ -16,20 +15,21 @@ pub struct GameScoreResult {
 impl GameScoreResult {
     /// Calculate total game score from spotted plates.
     /// Score is calculated based on the number of spotted plates and
-    /// any special achievement bonuses such as `West Coast`.
-    pub fn new(plates: &[SpottedPlate]) -> GameScoreResult {
+    /// bonuses of every achievement reached from the supplied registry.
+    pub fn new(plates: &[SpottedPlate], registry: &AchievementRegistry) -> GameScoreResult {
         let plates_hash: HashSet<_> = plates.iter().collect();
 
         let num_of_spotted_plates = plates_hash.len() as u32;
 
-        let (achievements, total_score) = [("West Coast", calc_west_coast_bonus(plates_hash))]
+        let (achievements, total_score) = registry
             .iter()
+            .map(|achievement| (achievement.name(), achievement.evaluate(&plates_hash)))
             .filter(|(_, (is_achieved, _))| *is_achieved)
             .fold(
                 (Vec::new(), num_of_spotted_plates),
                 |(mut achievements, mut total_score), (this_achievement, (_, this_score))| {
                     total_score += this_score;
-                    achievements.push(String::from(*this_achievement));
+                    achievements.push(String::from(this_achievement));
                     (achievements, total_score)
                 },
             );
@@ -42,40 +42,18 @@ impl GameScoreResult {
     }
 }
 
-static WEST_COAST_STATES: LazyLock<HashSet<&SpottedPlate>> = LazyLock::new(|| {
-    HashSet::from([
-        &SpottedPlate {
-            country: Country::US,
-            state_or_province: StateOrProvince::CA,
-        },
-        &SpottedPlate {
-            country: Country::US,
-            state_or_province: StateOrProvince::OR,
-        },
-        &SpottedPlate {
-            country: Country::US,
-            state_or_province: StateOrProvince::WA,
-        },
-    ])
-});
-
-fn calc_west_coast_bonus(plates: HashSet<&SpottedPlate>) -> (bool, u32) {
-    if WEST_COAST_STATES.is_subset(&plates) {
-        return (true, 30);
-    }
-
-    (false, 0)
-}
-
 #[cfg(test)]
 mod tests {
+    use super::super::achievements::Achievement;
+    use super::super::license_plate_enums::{Country, StateOrProvince};
     use super::*;
 
     #[test]
     fn will_return_zero_total_score_on_no_spots() {
         let spotted_plates = [];
 
-        let actual_score_result = GameScoreResult::new(&spotted_plates);
+        let actual_score_result =
+            GameScoreResult::new(&spotted_plates, &AchievementRegistry::default());
 
         assert_eq!(0, actual_score_result.num_of_spotted_plates);
         assert_eq!(0, actual_score_result.total_score);
@@ -95,7 +73,8 @@ mod tests {
             },
         ];
 
-        let actual_score_result = GameScoreResult::new(&spotted_plates);
+        let actual_score_result =
+            GameScoreResult::new(&spotted_plates, &AchievementRegistry::default());
 
         assert_eq!(2, actual_score_result.num_of_spotted_plates);
         assert_eq!(2, actual_score_result.total_score);
@@ -115,7 +94,8 @@ mod tests {
             },
         ];
 
-        let actual_score_result = GameScoreResult::new(&spotted_plates);
+        let actual_score_result =
+            GameScoreResult::new(&spotted_plates, &AchievementRegistry::default());
 
         assert_eq!(1, actual_score_result.num_of_spotted_plates);
         assert_eq!(1, actual_score_result.total_score);
@@ -139,7 +119,8 @@ mod tests {
             },
         ];
 
-        let actual_score_result = GameScoreResult::new(&spotted_plates);
+        let actual_score_result =
+            GameScoreResult::new(&spotted_plates, &AchievementRegistry::default());
 
         assert_eq!(3, actual_score_result.num_of_spotted_plates);
         assert_eq!(33, actual_score_result.total_score);
@@ -147,82 +128,76 @@ mod tests {
         assert_eq!("West Coast", actual_score_result.achievements[0]);
     }
 
-    #[test]
-    fn will_return_west_coast_bonus_when_achieved_exactly() {
-        let spotted_plates = HashSet::from([
-            &SpottedPlate {
-                country: Country::US,
-                state_or_province: StateOrProvince::CA,
-            },
-            &SpottedPlate {
-                country: Country::US,
-                state_or_province: StateOrProvince::OR,
-            },
-            &SpottedPlate {
+    fn to_spotted_plates(states: &[StateOrProvince]) -> Vec<SpottedPlate> {
+        states
+            .iter()
+            .map(|state_or_province| SpottedPlate {
                 country: Country::US,
-                state_or_province: StateOrProvince::WA,
-            },
-        ]);
-
-        let (actual_is_achieved, actual_score) = calc_west_coast_bonus(spotted_plates);
-
-        assert!(actual_is_achieved);
-        assert_eq!(30, actual_score);
+                state_or_province: *state_or_province,
+            })
+            .collect()
     }
 
     #[test]
-    fn will_return_west_coast_bonus_when_achieved_with_other_states() {
-        let spotted_plates = HashSet::from([
-            &SpottedPlate {
-                country: Country::US,
-                state_or_province: StateOrProvince::CA,
-            },
-            &SpottedPlate {
-                country: Country::US,
-                state_or_province: StateOrProvince::OR,
-            },
-            &SpottedPlate {
-                country: Country::US,
-                state_or_province: StateOrProvince::WA,
-            },
-            &SpottedPlate {
-                country: Country::US,
-                state_or_province: StateOrProvince::NV,
-            },
+    fn will_return_valid_total_score_on_coast_to_coast_achievement() {
+        let spotted_plates = to_spotted_plates(&[
+            StateOrProvince::WA,
+            StateOrProvince::ID,
+            StateOrProvince::MT,
+            StateOrProvince::ND,
+            StateOrProvince::MN,
+            StateOrProvince::WI,
+            StateOrProvince::MI,
+            StateOrProvince::OH,
+            StateOrProvince::PA,
+            StateOrProvince::NY,
         ]);
 
-        let (actual_is_achieved, actual_score) = calc_west_coast_bonus(spotted_plates);
+        let actual_score_result =
+            GameScoreResult::new(&spotted_plates, &AchievementRegistry::default());
 
-        assert!(actual_is_achieved);
-        assert_eq!(30, actual_score);
+        assert_eq!(10, actual_score_result.num_of_spotted_plates);
+        assert_eq!(10 + 100 + 10 * 2, actual_score_result.total_score);
+        assert_eq!(
+            vec!["Coast to Coast", "Longest Chain"],
+            actual_score_result.achievements
+        );
     }
 
-    #[test]
-    fn will_not_return_west_coast_bonus_when_not_achieved() {
-        let spotted_plates = HashSet::from([
-            &SpottedPlate {
-                country: Country::US,
-                state_or_province: StateOrProvince::CA,
-            },
-            &SpottedPlate {
-                country: Country::US,
-                state_or_province: StateOrProvince::OR,
-            },
-        ]);
+    struct AnySpotAchievement;
+
+    impl Achievement for AnySpotAchievement {
+        fn id(&self) -> &str {
+            "any_spot"
+        }
 
-        let (actual_is_achieved, actual_score) = calc_west_coast_bonus(spotted_plates);
+        fn name(&self) -> &str {
+            "First Spot"
+        }
 
-        assert!(!actual_is_achieved);
-        assert_eq!(0, actual_score);
+        fn description(&self) -> &str {
+            "Spot any plate"
+        }
+
+        fn evaluate(&self, plates: &HashSet<&SpottedPlate>) -> (bool, u32) {
+            (!plates.is_empty(), 5)
+        }
     }
 
     #[test]
-    fn will_not_return_west_coast_bonus_when_not_spots() {
-        let spotted_plates = HashSet::from([]);
+    fn will_return_valid_total_score_on_custom_registry() {
+        let registry = AchievementRegistry::empty().with_achievement(Box::new(AnySpotAchievement));
 
-        let (actual_is_achieved, actual_score) = calc_west_coast_bonus(spotted_plates);
+        let spotted_plates = to_spotted_plates(&[
+            StateOrProvince::CA,
+            StateOrProvince::OR,
+            StateOrProvince::WA,
+        ]);
 
-        assert!(!actual_is_achieved);
-        assert_eq!(0, actual_score);
+        let actual_score_result = GameScoreResult::new(&spotted_plates, &registry);
+
+        assert_eq!(3, actual_score_result.num_of_spotted_plates);
+        assert_eq!(8, actual_score_result.total_score);
+        assert_eq!(vec!["First Spot"], actual_score_result.achievements);
     }
 }
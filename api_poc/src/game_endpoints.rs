@@ -0,0 +1,547 @@
+use std::sync::Arc;
+
+use actix_web::{delete, get, post, web, HttpResponse};
+use bson::{oid::ObjectId, DateTime};
+use log::info;
+use mongodb::Database;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    api_error::{ApiError, ApiResult},
+    auth::authenticated_player::{AuthenticatedPlayer, ExistingPlayer},
+    game::{
+        achievements::AchievementRegistry,
+        game::{Game, GameStatus},
+        license_plate_enums::{Country, StateOrProvince},
+        license_plates::SpottedPlate,
+        plate_spot::{PlateSpot, SpotLocation},
+        score_calculator::GameScoreResult,
+    },
+    AppState,
+};
+
+/// Game representation returned to API clients.
+/// Game score is always calculated server-side from spotted plates.
+#[derive(Serialize)]
+pub struct GameView {
+    game_id: String,
+    owner_id: String,
+    participants: Vec<String>,
+    invited_players: Vec<String>,
+    spots: Vec<SpotView>,
+    status: GameStatus,
+    date_created: String,
+    date_ended: Option<String>,
+    score: GameScoreResult,
+}
+
+impl GameView {
+    fn new(game: Game, achievements: &AchievementRegistry) -> Self {
+        let score = GameScoreResult::new(&game.get_spotted_plates(), achievements);
+
+        Self {
+            game_id: game.id.to_hex(),
+            owner_id: game.owner_id.to_hex(),
+            participants: game
+                .participants
+                .into_iter()
+                .map(ObjectId::to_hex)
+                .collect(),
+            invited_players: game
+                .invited_players
+                .into_iter()
+                .map(ObjectId::to_hex)
+                .collect(),
+            spots: game.spots.into_iter().map(SpotView::from).collect(),
+            status: game.status,
+            date_created: to_rfc3339(game.date_created),
+            date_ended: game.date_ended.map(to_rfc3339),
+            score,
+        }
+    }
+}
+
+/// Plate spot representation returned to API clients
+#[derive(Serialize)]
+pub struct SpotView {
+    #[serde(flatten)]
+    plate: SpottedPlate,
+    spotted_by: String,
+    date_spotted: String,
+    client_date_spotted: Option<String>,
+    location: Option<SpotLocation>,
+}
+
+impl From<PlateSpot> for SpotView {
+    fn from(spot: PlateSpot) -> Self {
+        Self {
+            plate: spot.plate,
+            spotted_by: spot.spotted_by.to_hex(),
+            date_spotted: to_rfc3339(spot.date_spotted),
+            client_date_spotted: spot.client_date_spotted.map(to_rfc3339),
+            location: spot.location,
+        }
+    }
+}
+
+pub fn to_rfc3339(date: DateTime) -> String {
+    date.try_to_rfc3339_string().unwrap_or_default()
+}
+
+#[derive(Deserialize)]
+struct SpotPath {
+    game_id: String,
+    country: Country,
+    state_or_province: StateOrProvince,
+}
+
+#[derive(Deserialize)]
+struct SpotRequest {
+    #[serde(flatten)]
+    plate: SpottedPlate,
+    /// Device time in RFC 3339 format
+    client_date_spotted: Option<String>,
+    location: Option<SpotLocation>,
+}
+
+#[derive(Deserialize)]
+struct InvitationRequest {
+    player_id: String,
+}
+
+/// Parse game id from the request path or body
+fn parse_game_id(game_id: &str) -> ApiResult<ObjectId> {
+    ObjectId::parse_str(game_id).map_err(|_| ApiError::Validation("invalid game id!".into()))
+}
+
+/// Build a response from a game update result.
+/// Missing game means it either doesn't exist, is not accessible by the player, or is no longer active.
+fn game_update_response(
+    game_id: ObjectId,
+    achievements: &AchievementRegistry,
+    updated_game: Option<Game>,
+) -> ApiResult<HttpResponse> {
+    let game =
+        updated_game.ok_or_else(|| ApiError::NotFound(format!("game {game_id} was not found!")))?;
+
+    Ok(HttpResponse::Ok().json(GameView::new(game, achievements)))
+}
+
+#[post("/games")]
+async fn create_game(
+    data: web::Data<Arc<AppState>>,
+    db: web::Data<Arc<Database>>,
+    ExistingPlayer(player): ExistingPlayer,
+) -> ApiResult<HttpResponse> {
+    // game owner must still exist
+    let player_id = player.id;
+
+    info!("Creating new game for player {player_id}...");
+
+    let game = Game::create_new_game(&db, player_id).await?;
+
+    Ok(HttpResponse::Created().json(GameView::new(game, &data.achievements)))
+}
+
+#[get("/games")]
+async fn get_my_games(
+    data: web::Data<Arc<AppState>>,
+    db: web::Data<Arc<Database>>,
+    player: AuthenticatedPlayer,
+) -> ApiResult<HttpResponse> {
+    let player_id = player.id;
+
+    let game_views: Vec<GameView> = Game::get_games_for_player(&db, player_id)
+        .await?
+        .into_iter()
+        .map(|game| GameView::new(game, &data.achievements))
+        .collect();
+
+    Ok(HttpResponse::Ok().json(game_views))
+}
+
+#[get("/games/{game_id}")]
+async fn get_game(
+    data: web::Data<Arc<AppState>>,
+    db: web::Data<Arc<Database>>,
+    game_id: web::Path<String>,
+    player: AuthenticatedPlayer,
+) -> ApiResult<HttpResponse> {
+    let player_id = player.id;
+    let game_id = parse_game_id(&game_id)?;
+
+    // games are visible to participants only
+    let game = Game::get_game_by_id(&db, game_id)
+        .await?
+        .filter(|game| game.participants.contains(&player_id));
+
+    game_update_response(game_id, &data.achievements, game)
+}
+
+#[post("/games/{game_id}/spots")]
+async fn add_spot(
+    data: web::Data<Arc<AppState>>,
+    db: web::Data<Arc<Database>>,
+    game_id: web::Path<String>,
+    req_body: web::Json<SpotRequest>,
+    player: AuthenticatedPlayer,
+) -> ApiResult<HttpResponse> {
+    let player_id = player.id;
+    let game_id = parse_game_id(&game_id)?;
+
+    let spot_request = req_body.into_inner();
+    if !spot_request.plate.is_valid_game_plate() {
+        return Err(ApiError::Validation("invalid plate!".into()));
+    }
+
+    if spot_request
+        .location
+        .is_some_and(|location| !location.is_valid())
+    {
+        return Err(ApiError::Validation("invalid location!".into()));
+    }
+
+    let client_date_spotted = spot_request
+        .client_date_spotted
+        .map(DateTime::parse_rfc3339_str)
+        .transpose()
+        .map_err(|_| ApiError::Validation("invalid client date!".into()))?;
+
+    let spot = PlateSpot::new(
+        spot_request.plate,
+        player_id,
+        client_date_spotted,
+        spot_request.location,
+    );
+
+    let updated_game = Game::add_spot(&db, game_id, &spot).await?;
+
+    game_update_response(game_id, &data.achievements, updated_game)
+}
+
+#[delete("/games/{game_id}/spots/{country}/{state_or_province}")]
+async fn remove_spot(
+    data: web::Data<Arc<AppState>>,
+    db: web::Data<Arc<Database>>,
+    spot_path: web::Path<SpotPath>,
+    player: AuthenticatedPlayer,
+) -> ApiResult<HttpResponse> {
+    let player_id = player.id;
+
+    let spot_path = spot_path.into_inner();
+    let game_id = parse_game_id(&spot_path.game_id)?;
+
+    let plate = SpottedPlate {
+        country: spot_path.country,
+        state_or_province: spot_path.state_or_province,
+    };
+
+    let updated_game = Game::remove_spot(&db, game_id, player_id, &plate).await?;
+
+    game_update_response(game_id, &data.achievements, updated_game)
+}
+
+#[post("/games/{game_id}/end")]
+async fn end_game(
+    data: web::Data<Arc<AppState>>,
+    db: web::Data<Arc<Database>>,
+    game_id: web::Path<String>,
+    player: AuthenticatedPlayer,
+) -> ApiResult<HttpResponse> {
+    let player_id = player.id;
+    let game_id = parse_game_id(&game_id)?;
+
+    let updated_game = Game::end_game(&db, game_id, player_id).await?;
+
+    game_update_response(game_id, &data.achievements, updated_game)
+}
+
+#[post("/games/{game_id}/invitations")]
+async fn invite_player(
+    data: web::Data<Arc<AppState>>,
+    db: web::Data<Arc<Database>>,
+    game_id: web::Path<String>,
+    req_body: web::Json<InvitationRequest>,
+    player: AuthenticatedPlayer,
+) -> ApiResult<HttpResponse> {
+    if player.is_guest {
+        return Err(ApiError::Forbidden(
+            "guest players can't invite other players!".into(),
+        ));
+    }
+
+    let player_id = player.id;
+    let game_id = parse_game_id(&game_id)?;
+
+    let invitee_id = ObjectId::parse_str(&req_body.player_id)
+        .map_err(|_| ApiError::Validation("invalid player id!".into()))?;
+
+    let game = Game::get_game_by_id(&db, game_id)
+        .await?
+        .filter(|game| game.participants.contains(&player_id))
+        .ok_or_else(|| ApiError::NotFound(format!("game {game_id} was not found!")))?;
+
+    if game.owner_id != player_id {
+        return Err(ApiError::Forbidden(
+            "only game owner can invite players!".into(),
+        ));
+    }
+
+    if game.participants.contains(&invitee_id) {
+        return Err(ApiError::Conflict(
+            "player already participates in the game!".into(),
+        ));
+    }
+
+    info!("Inviting player {invitee_id} to game {game_id}...");
+
+    let updated_game = Game::invite_player(&db, game_id, player_id, invitee_id).await?;
+
+    game_update_response(game_id, &data.achievements, updated_game)
+}
+
+#[get("/invitations")]
+async fn get_my_invitations(
+    data: web::Data<Arc<AppState>>,
+    db: web::Data<Arc<Database>>,
+    player: AuthenticatedPlayer,
+) -> ApiResult<HttpResponse> {
+    let player_id = player.id;
+
+    let game_views: Vec<GameView> = Game::get_pending_invitations(&db, player_id)
+        .await?
+        .into_iter()
+        .map(|game| GameView::new(game, &data.achievements))
+        .collect();
+
+    Ok(HttpResponse::Ok().json(game_views))
+}
+
+#[post("/invitations/{game_id}/accept")]
+async fn accept_invitation(
+    data: web::Data<Arc<AppState>>,
+    db: web::Data<Arc<Database>>,
+    game_id: web::Path<String>,
+    player: AuthenticatedPlayer,
+) -> ApiResult<HttpResponse> {
+    let player_id = player.id;
+    let game_id = parse_game_id(&game_id)?;
+
+    let updated_game = Game::accept_invitation(&db, game_id, player_id).await?;
+
+    game_update_response(game_id, &data.achievements, updated_game)
+}
+
+#[post("/invitations/{game_id}/decline")]
+async fn decline_invitation(
+    db: web::Data<Arc<Database>>,
+    game_id: web::Path<String>,
+    player: AuthenticatedPlayer,
+) -> ApiResult<HttpResponse> {
+    let player_id = player.id;
+    let game_id = parse_game_id(&game_id)?;
+
+    Game::decline_invitation(&db, game_id, player_id)
+        .await?
+        .ok_or_else(|| {
+            ApiError::NotFound(format!("invitation to game {game_id} was not found!"))
+        })?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Configure game endpoints. Must be registered within `/api` scope.
+pub fn game_config(cfg: &mut web::ServiceConfig) {
+    cfg.service(create_game)
+        .service(get_my_games)
+        .service(get_game)
+        .service(add_spot)
+        .service(remove_spot)
+        .service(end_game)
+        .service(invite_player)
+        .service(get_my_invitations)
+        .service(accept_invitation)
+        .service(decline_invitation);
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{dev::Service as _, http::StatusCode, test, App, HttpMessage as _};
+    use mongodb::Client;
+
+    use crate::{
+        api_endpoints,
+        auth::{role_authorization::GUEST_ROLE, token_service::UserClaims},
+        test_helpers::{create_test_app_state, create_test_claims},
+    };
+
+    use super::*;
+
+    /// Call `/api` with the supplied claims standing in for a validated token.
+    /// Requests are expected to be rejected before reaching the database.
+    async fn call_api(req: test::TestRequest, claims: Option<UserClaims>) -> StatusCode {
+        let app_state = Arc::new(create_test_app_state());
+
+        // client connects lazily, so nothing has to listen on this port
+        let mongo_database = Client::with_uri_str("mongodb://localhost:1")
+            .await
+            .unwrap()
+            .database("test_db");
+
+        let uut_app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(app_state))
+                .app_data(web::Data::new(Arc::new(mongo_database)))
+                .wrap_fn(move |req, srv| {
+                    if let Some(claims) = claims.clone() {
+                        req.extensions_mut().insert(claims);
+                    }
+                    srv.call(req)
+                })
+                .service(web::scope("/api").configure(api_endpoints::api_config)),
+        )
+        .await;
+
+        test::call_service(&uut_app, req.to_request())
+            .await
+            .status()
+    }
+
+    #[actix_web::test]
+    async fn will_return_401_without_claims() {
+        let actual_status = call_api(test::TestRequest::get().uri("/api/games"), None).await;
+
+        assert_eq!(StatusCode::UNAUTHORIZED, actual_status);
+    }
+
+    #[actix_web::test]
+    async fn will_return_400_on_invalid_game_id() {
+        let actual_status = call_api(
+            test::TestRequest::get().uri("/api/games/not_a_game_id"),
+            Some(create_test_claims(&ObjectId::new().to_hex())),
+        )
+        .await;
+
+        assert_eq!(StatusCode::BAD_REQUEST, actual_status);
+    }
+
+    #[actix_web::test]
+    async fn will_return_400_on_invalid_plate() {
+        let actual_status = call_api(
+            test::TestRequest::post()
+                .uri(&format!("/api/games/{}/spots", ObjectId::new().to_hex()))
+                .set_json(serde_json::json!({ "country": "CA", "state_or_province": "WA" })),
+            Some(create_test_claims(&ObjectId::new().to_hex())),
+        )
+        .await;
+
+        assert_eq!(StatusCode::BAD_REQUEST, actual_status);
+    }
+
+    #[actix_web::test]
+    async fn will_return_400_on_invalid_spot_location() {
+        let actual_status = call_api(
+            test::TestRequest::post()
+                .uri(&format!("/api/games/{}/spots", ObjectId::new().to_hex()))
+                .set_json(serde_json::json!({
+                    "country": "US",
+                    "state_or_province": "WA",
+                    "location": { "latitude": 91.0, "longitude": 0.0 },
+                })),
+            Some(create_test_claims(&ObjectId::new().to_hex())),
+        )
+        .await;
+
+        assert_eq!(StatusCode::BAD_REQUEST, actual_status);
+    }
+
+    #[actix_web::test]
+    async fn will_return_400_on_invalid_client_date() {
+        let actual_status = call_api(
+            test::TestRequest::post()
+                .uri(&format!("/api/games/{}/spots", ObjectId::new().to_hex()))
+                .set_json(serde_json::json!({
+                    "country": "US",
+                    "state_or_province": "WA",
+                    "client_date_spotted": "yesterday",
+                })),
+            Some(create_test_claims(&ObjectId::new().to_hex())),
+        )
+        .await;
+
+        assert_eq!(StatusCode::BAD_REQUEST, actual_status);
+    }
+
+    #[actix_web::test]
+    async fn will_return_400_on_malformed_spot_body() {
+        let actual_status = call_api(
+            test::TestRequest::post()
+                .uri(&format!("/api/games/{}/spots", ObjectId::new().to_hex()))
+                .set_json(serde_json::json!({ "country": "XX" })),
+            Some(create_test_claims(&ObjectId::new().to_hex())),
+        )
+        .await;
+
+        assert_eq!(StatusCode::BAD_REQUEST, actual_status);
+    }
+
+    #[actix_web::test]
+    async fn will_return_400_on_unknown_plate_in_path() {
+        let actual_status = call_api(
+            test::TestRequest::delete().uri(&format!(
+                "/api/games/{}/spots/XX/WA",
+                ObjectId::new().to_hex()
+            )),
+            Some(create_test_claims(&ObjectId::new().to_hex())),
+        )
+        .await;
+
+        assert_eq!(StatusCode::BAD_REQUEST, actual_status);
+    }
+
+    #[actix_web::test]
+    async fn will_return_400_on_invalid_invitee_id() {
+        let actual_status = call_api(
+            test::TestRequest::post()
+                .uri(&format!(
+                    "/api/games/{}/invitations",
+                    ObjectId::new().to_hex()
+                ))
+                .set_json(serde_json::json!({ "player_id": "not_a_player_id" })),
+            Some(create_test_claims(&ObjectId::new().to_hex())),
+        )
+        .await;
+
+        assert_eq!(StatusCode::BAD_REQUEST, actual_status);
+    }
+
+    #[actix_web::test]
+    async fn will_return_403_on_guest_invitation() {
+        let actual_status = call_api(
+            test::TestRequest::post()
+                .uri(&format!(
+                    "/api/games/{}/invitations",
+                    ObjectId::new().to_hex()
+                ))
+                .set_json(serde_json::json!({ "player_id": ObjectId::new().to_hex() })),
+            Some(UserClaims {
+                roles: vec![GUEST_ROLE.into()],
+                ..create_test_claims(&ObjectId::new().to_hex())
+            }),
+        )
+        .await;
+
+        assert_eq!(StatusCode::FORBIDDEN, actual_status);
+    }
+
+    #[actix_web::test]
+    async fn will_return_400_on_invalid_invitation_game_id() {
+        let actual_status = call_api(
+            test::TestRequest::post().uri("/api/invitations/not_a_game_id/accept"),
+            Some(create_test_claims(&ObjectId::new().to_hex())),
+        )
+        .await;
+
+        assert_eq!(StatusCode::BAD_REQUEST, actual_status);
+    }
+}
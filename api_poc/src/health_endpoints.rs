@@ -0,0 +1,191 @@
+use std::{collections::BTreeMap, future::Future, sync::Arc, time::Duration};
+
+use actix_web::{get, rt::time::timeout, web, HttpResponse, Responder};
+use bson::doc;
+use log::error;
+use mongodb::Database;
+use serde::Serialize;
+
+use crate::{api_error::ApiResult, game::player::Player, AppState};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum HealthStatus {
+    Up,
+    Down,
+}
+
+#[derive(Debug, Serialize)]
+struct ComponentHealth {
+    status: HealthStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    detail: Option<&'static str>,
+}
+
+impl ComponentHealth {
+    fn up() -> Self {
+        Self {
+            status: HealthStatus::Up,
+            detail: None,
+        }
+    }
+
+    fn down(detail: &'static str) -> Self {
+        Self {
+            status: HealthStatus::Down,
+            detail: Some(detail),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct HealthReport {
+    status: HealthStatus,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    components: BTreeMap<&'static str, ComponentHealth>,
+}
+
+/// Run component check with a timeout.
+/// Failure details are logged only, so anonymous callers don't see internal errors.
+async fn check_component(
+    component: &str,
+    check_timeout: Duration,
+    check: impl Future<Output = ApiResult<bool>>,
+) -> ComponentHealth {
+    match timeout(check_timeout, check).await {
+        Ok(Ok(true)) => ComponentHealth::up(),
+        Ok(Ok(false)) => ComponentHealth::down("check failed"),
+        Ok(Err(err)) => {
+            error!("{component} health check failed: {err}");
+            ComponentHealth::down("check failed")
+        }
+        Err(_) => {
+            error!("{component} health check timed out!");
+            ComponentHealth::down("check timed out")
+        }
+    }
+}
+
+/// Liveness probe. Api process is up and serving requests
+#[get("/live")]
+async fn live() -> impl Responder {
+    HttpResponse::Ok().json(HealthReport {
+        status: HealthStatus::Up,
+        components: BTreeMap::new(),
+    })
+}
+
+/// Readiness probe. Api can serve requests that depend on the database
+#[get("/ready")]
+async fn ready(data: web::Data<Arc<AppState>>, db: web::Data<Arc<Database>>) -> impl Responder {
+    let check_timeout = Duration::from_millis(data.config.health_check_timeout_ms);
+
+    let mongo_health = check_component("mongo", check_timeout, async {
+        db.run_command(doc! { "ping": 1 }).await?;
+        Ok(true)
+    })
+    .await;
+
+    // there is no point checking indexes when db is not reachable
+    let identity_index_health = if mongo_health.status == HealthStatus::Up {
+        check_component(
+            "player identity index",
+            check_timeout,
+            Player::has_identity_index(&db),
+        )
+        .await
+    } else {
+        ComponentHealth::down("mongo is not available")
+    };
+
+    let components = BTreeMap::from([
+        ("mongo", mongo_health),
+        ("player_identity_index", identity_index_health),
+    ]);
+
+    let status = if components
+        .values()
+        .all(|component| component.status == HealthStatus::Up)
+    {
+        HealthStatus::Up
+    } else {
+        HealthStatus::Down
+    };
+
+    let report = HealthReport { status, components };
+
+    match status {
+        HealthStatus::Up => HttpResponse::Ok().json(report),
+        HealthStatus::Down => HttpResponse::ServiceUnavailable().json(report),
+    }
+}
+
+/// Configure health endpoints. Must be registered within `/health` scope and allowed for anonymous access.
+pub fn health_config(cfg: &mut web::ServiceConfig) {
+    cfg.service(live).service(ready);
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{http::StatusCode, test, App};
+    use mongodb::Client;
+
+    use crate::{app_config::AppConfig, test_helpers::create_test_app_state};
+
+    use super::*;
+
+    #[actix_web::test]
+    async fn will_return_200_on_live() {
+        let uut_app =
+            test::init_service(App::new().service(web::scope("/health").configure(health_config)))
+                .await;
+
+        let req = test::TestRequest::get().uri("/health/live").to_request();
+
+        let actual_resp: serde_json::Value = test::call_and_read_body_json(&uut_app, req).await;
+
+        assert_eq!(serde_json::json!({ "status": "up" }), actual_resp);
+    }
+
+    #[actix_web::test]
+    async fn will_return_503_on_unavailable_mongo() {
+        let app_state = Arc::new(AppState {
+            config: AppConfig {
+                health_check_timeout_ms: 200,
+                ..AppConfig::default()
+            },
+            ..create_test_app_state()
+        });
+
+        // nothing listens on this port, so the ping will never succeed
+        let unavailable_db = Client::with_uri_str(
+            "mongodb://localhost:1/?directConnection=true&serverSelectionTimeoutMS=100",
+        )
+        .await
+        .unwrap()
+        .database("game_api");
+
+        let uut_app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(app_state))
+                .app_data(web::Data::new(Arc::new(unavailable_db)))
+                .service(web::scope("/health").configure(health_config)),
+        )
+        .await;
+
+        let req = test::TestRequest::get().uri("/health/ready").to_request();
+
+        let actual_resp = test::call_service(&uut_app, req).await;
+
+        assert_eq!(StatusCode::SERVICE_UNAVAILABLE, actual_resp.status());
+
+        let actual_body: serde_json::Value = test::read_body_json(actual_resp).await;
+
+        assert_eq!("down", actual_body["status"]);
+        assert_eq!("down", actual_body["components"]["mongo"]["status"]);
+        assert_eq!(
+            "mongo is not available",
+            actual_body["components"]["player_identity_index"]["detail"]
+        );
+    }
+}
@@ -1,17 +1,47 @@
 use std::sync::Arc;
 
+use actix_web::http::Method;
 use app_config::AppConfig;
 use auth::{
-    jwt_auth_middleware::JwtAuthentication,
+    identity_provider::IdentityProviderRegistry,
+    jwt_auth_middleware::{AnonymousRoute, JwtAuthentication},
+    signing_key::JwtKeySet,
+    token_revocation::{RevokedToken, RevokedTokenCache},
     token_service::{JwtTokenService, TokenService},
 };
-use game::player::Player;
-use log::info;
+use game::{achievements::AchievementRegistry, player::Player};
+use log::{error, info};
+use mongodb::bson::DateTime;
+use rate_limit::{
+    rate_limit_middleware::RateLimiter,
+    rate_limit_store::{InMemoryRateLimitStore, MongoRateLimitStore, RateLimitStore},
+};
 
+mod admin_endpoints;
 mod api_endpoints;
+mod api_error;
 mod app_config;
 mod auth;
 mod game;
+mod game_endpoints;
+mod health_endpoints;
+mod player_endpoints;
+mod rate_limit;
+#[cfg(test)]
+mod test_helpers;
+mod well_known_endpoints;
+
+/// Guest retention is measured in days, so hourly purge is precise enough
+const GUEST_PURGE_INTERVAL_SEC: u64 = 3600;
+
+/// Health probes poll at a fixed rate, and jwks is cached by token consumers,
+/// so neither is limited nor charged to the buckets shared with token endpoints
+fn get_rate_limit_excluded_routes() -> Vec<AnonymousRoute> {
+    vec![
+        AnonymousRoute::prefix("/health").with_method(Method::GET),
+        AnonymousRoute::path("/.well-known/jwks.json").with_method(Method::GET),
+    ]
+}
 
 struct AppState {
     config: AppConfig,
@@ -20,6 +50,12 @@ struct AppState {
     /// allowing token service implementation to be known at the runtime rather than compile time.
     /// This is not strictly necessary for this project.
     token_service: Box<dyn TokenService>,
+    /// Identity providers used to establish player identity
+    identity_providers: IdentityProviderRegistry,
+    /// Achievements used to calculate game scores
+    achievements: AchievementRegistry,
+    /// Revoked api tokens checked by the auth middleware
+    revoked_tokens: RevokedTokenCache,
 }
 
 #[actix_web::main]
@@ -38,6 +74,21 @@ async fn main() -> std::io::Result<()> {
     let config = AppConfig::build_config().expect("Failed to load configuration");
     let bind_host = (config.host_ip.clone(), config.port);
 
+    // achievement definitions are validated at startup so bad definitions never reach scoring
+    let achievements = match &config.achievements_file {
+        Some(achievements_file) => {
+            info!("loading achievements from {achievements_file}...");
+            AchievementRegistry::load_from_file(achievements_file)
+                .expect("Failed to load achievements")
+        }
+        None => AchievementRegistry::default(),
+    };
+
+    let jwt_key_set = JwtKeySet::from_config(&config).expect("Failed to load jwt signing keys");
+
+    let identity_providers = IdentityProviderRegistry::from_config(&config)
+        .expect("Failed to configure identity providers");
+
     info!("attempting to connect to mongo...");
     let game_api_mongo_db = Client::with_uri_str(&config.mongo_connection_string)
         .await
@@ -48,29 +99,136 @@ async fn main() -> std::io::Result<()> {
         .await
         .expect("Failed to create player index");
 
+    let _ = Player::create_linked_identity_index(&game_api_mongo_db)
+        .await
+        .expect("Failed to create player linked identity index");
+
+    let _ = RevokedToken::create_ttl_index(&game_api_mongo_db)
+        .await
+        .expect("Failed to create revoked tokens index");
+
+    let revoked_tokens = RevokedTokenCache::default();
+    revoked_tokens
+        .sync(&game_api_mongo_db)
+        .await
+        .expect("Failed to load revoked tokens");
+
+    // limiter state is shared by all workers, otherwise each worker would have its own buckets
+    let rate_limit_store: Arc<dyn RateLimitStore> = if config.rate_limit.use_mongo_store {
+        let _ = MongoRateLimitStore::create_ttl_index(&game_api_mongo_db)
+            .await
+            .expect("Failed to create rate limit buckets index");
+
+        Arc::new(MongoRateLimitStore::new(game_api_mongo_db.clone()))
+    } else {
+        Arc::new(InMemoryRateLimitStore::default())
+    };
+
     info!("mongo connected. initializing api handlers");
 
     let app_state = Arc::new(AppState {
         token_service: Box::new(JwtTokenService::new(
-            &config.jwt_signing_key,
+            jwt_key_set,
             &config.appname,
             &config.appname,
             1,
             config.token_lifetime_min,
         )),
+        identity_providers,
+        achievements,
+        revoked_tokens,
         config,
     });
 
     let game_api_mongo_db = Arc::new(game_api_mongo_db);
 
+    // pick up tokens revoked by other api instances
+    let sync_state = app_state.clone();
+    let sync_db = game_api_mongo_db.clone();
+    actix_web::rt::spawn(async move {
+        let mut sync_interval = actix_web::rt::time::interval(std::time::Duration::from_secs(
+            sync_state.config.revoked_tokens_sync_interval_sec,
+        ));
+
+        loop {
+            sync_interval.tick().await;
+
+            if let Err(err) = sync_state.revoked_tokens.sync(&sync_db).await {
+                error!("failed to sync revoked tokens: {err}");
+            }
+        }
+    });
+
+    // purge guest players that stopped playing
+    let guest_retention = chrono::Duration::days(app_state.config.guest_retention_days as i64);
+    let purge_state = app_state.clone();
+    let purge_db = game_api_mongo_db.clone();
+    actix_web::rt::spawn(async move {
+        let mut purge_interval =
+            actix_web::rt::time::interval(std::time::Duration::from_secs(GUEST_PURGE_INTERVAL_SEC));
+
+        loop {
+            purge_interval.tick().await;
+
+            let inactive_since =
+                DateTime::from_millis((chrono::Utc::now() - guest_retention).timestamp_millis());
+
+            let purged_ids = match Player::purge_inactive_guests(&purge_db, inactive_since).await {
+                Ok(purged_ids) => purged_ids,
+                Err(err) => {
+                    error!("failed to purge inactive guest players: {err}");
+                    continue;
+                }
+            };
+
+            if purged_ids.is_empty() {
+                continue;
+            }
+
+            info!("purged {} inactive guest players", purged_ids.len());
+
+            if let Err(err) =
+                api_endpoints::revoke_player_tokens(&purge_state, &purge_db, &purged_ids).await
+            {
+                error!("failed to revoke purged guest player tokens: {err}");
+            }
+        }
+    });
+
     // actix will call this function for the requested number of handlers (default == num of cores)
     HttpServer::new(move || {
         let api_scope = web::scope("/api").configure(api_endpoints::api_config);
+        let health_scope = web::scope("/health").configure(health_endpoints::health_config);
+        let well_known_scope =
+            web::scope("/.well-known").configure(well_known_endpoints::well_known_config);
 
         App::new()
             // middleware is executed in LIFO (stack) order
             .wrap(middleware::Compress::default())
-            .wrap(JwtAuthentication::new(vec!["/api/token".into()])) // must be wrapped first to avoid compilation errors
+            // limiter runs after auth, so authenticated requests are limited per token subject
+            .wrap(middleware::Condition::new(
+                app_state.config.rate_limit.enabled,
+                RateLimiter::new(
+                    rate_limit_store.clone(),
+                    app_state.config.rate_limit.clone(),
+                )
+                .with_excluded_routes(get_rate_limit_excluded_routes()),
+            ))
+            .wrap(JwtAuthentication::new(vec![
+                AnonymousRoute::path("/api/token").with_method(Method::POST),
+                AnonymousRoute::path("/api/token/refresh").with_method(Method::POST),
+                AnonymousRoute::prefix("/health").with_method(Method::GET),
+                AnonymousRoute::path("/.well-known/jwks.json").with_method(Method::GET),
+            ])) // must be wrapped first to avoid compilation errors
+            // limiter runs before auth, so requests failing authentication are limited per client ip too
+            .wrap(middleware::Condition::new(
+                app_state.config.rate_limit.enabled,
+                RateLimiter::per_client_ip(
+                    rate_limit_store.clone(),
+                    app_state.config.rate_limit.clone(),
+                )
+                .with_excluded_routes(get_rate_limit_excluded_routes()),
+            ))
             // log each request. See https://docs.rs/actix-web/4.2.1/actix_web/middleware/struct.Logger.html#format
             // ex:
             // first line of request + response status + time take to serve request in ms
@@ -79,6 +237,8 @@ async fn main() -> std::io::Result<()> {
             .app_data(web::Data::new(app_state.clone()))
             .app_data(web::Data::new(game_api_mongo_db.clone()))
             .service(api_scope)
+            .service(health_scope)
+            .service(well_known_scope)
     })
     .bind(bind_host)
     .expect("Address and port should be free and valid")
@@ -0,0 +1,100 @@
+use std::sync::Arc;
+
+use actix_web::{post, web, HttpResponse};
+use log::{error, info};
+use mongodb::Database;
+use serde::Deserialize;
+
+use crate::{
+    api_endpoints::revoke_player_tokens,
+    api_error::{ApiError, ApiResult},
+    auth::{
+        authenticated_player::AuthenticatedPlayer, identity_provider::get_default_provider_name,
+    },
+    game::player::{Player, GUEST_PROVIDER_NAME},
+    AppState,
+};
+
+/// Identity the player proves ownership of, by signing in with the provider
+#[derive(Deserialize)]
+struct IdentityRequest {
+    #[serde(default = "get_default_provider_name")]
+    provider: String,
+    authorization_code: String,
+}
+
+/// Verify identity with the provider. Returns provider name and provider identity id
+async fn get_verified_identity(
+    data: &AppState,
+    identity_request: &IdentityRequest,
+) -> ApiResult<(String, String)> {
+    let identity_provider = data
+        .identity_providers
+        .get_provider(&identity_request.provider)?;
+    let provider_name = identity_provider.get_provider_name();
+
+    let identity = identity_provider
+        .get_identity(&identity_request.authorization_code)
+        .await
+        .inspect_err(|err| error!("failed to establish {provider_name} player identity: {err}"))?;
+
+    Ok((provider_name.into(), identity.sub))
+}
+
+/// Link another provider identity, so it signs in as the current player
+#[post("/players/me/identities")]
+async fn link_identity(
+    data: web::Data<Arc<AppState>>,
+    db: web::Data<Arc<Database>>,
+    req_body: web::Json<IdentityRequest>,
+    player: AuthenticatedPlayer,
+) -> ApiResult<HttpResponse> {
+    // checked before the provider call, so guest device secrets are never verified for linking
+    if req_body.provider == GUEST_PROVIDER_NAME {
+        return Err(ApiError::Validation(
+            "guest identity can't be linked to a player!".into(),
+        ));
+    }
+
+    let (provider_name, provider_identity_id) = get_verified_identity(&data, &req_body).await?;
+
+    info!(
+        "Linking {provider_name} identity to player {}...",
+        player.id
+    );
+
+    Player::link_identity(&db, player.id, &provider_name, &provider_identity_id).await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Merge player signed in with another identity into the current player.
+/// Signing in with the other identity proves both players belong to the same person.
+#[post("/players/me/merge")]
+async fn merge_player(
+    data: web::Data<Arc<AppState>>,
+    db: web::Data<Arc<Database>>,
+    req_body: web::Json<IdentityRequest>,
+    player: AuthenticatedPlayer,
+) -> ApiResult<HttpResponse> {
+    let (provider_name, provider_identity_id) = get_verified_identity(&data, &req_body).await?;
+
+    let source_player =
+        Player::get_player_by_existing_identity(&db, &provider_name, &provider_identity_id)
+            .await?
+            .ok_or_else(|| {
+                ApiError::NotFound(format!("no player found for {provider_name} identity!"))
+            })?;
+
+    info!("Merging player {} into {}...", source_player.id, player.id);
+
+    Player::merge_players(&db, player.id, source_player.id).await?;
+    revoke_player_tokens(&data, &db, &[source_player.id]).await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Configure player endpoints.
+pub fn player_config(cfg: &mut web::ServiceConfig) {
+    cfg.service(link_identity).service(merge_player);
+}
@@ -0,0 +1,3 @@
+pub mod rate_limit_middleware;
+
+pub mod rate_limit_store;
@@ -0,0 +1,376 @@
+use std::{
+    future::{ready, Ready},
+    rc::Rc,
+    sync::Arc,
+};
+
+use actix_web::{
+    body::EitherBody,
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    Error, HttpMessage, ResponseError,
+};
+use futures_util::future::LocalBoxFuture;
+use log::{error, warn};
+
+use crate::{
+    api_error::ApiError,
+    app_config::RateLimitConfig,
+    auth::{jwt_auth_middleware::AnonymousRoute, token_service::UserClaims},
+    rate_limit::rate_limit_store::{RateLimitDecision, RateLimitStore},
+};
+
+/// How the limiter identifies clients
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum BucketKeySource {
+    /// Token subject for authenticated requests, client ip for anonymous ones
+    Subject,
+    /// Client ip for every request, whether authenticated or not
+    ClientIp,
+}
+
+/// Token bucket rate limiter.
+/// Subject limiter must be wrapped before `JwtAuthentication`, so it runs after the token is validated
+/// and authenticated requests are limited per token subject rather than per client ip.
+/// Client ip limiter must be wrapped after `JwtAuthentication`, so requests failing authentication are limited too.
+/// ex:
+/// ```ignore
+/// App::new()
+///     .wrap(RateLimiter::new(store.clone(), config.clone()))
+///     .wrap(JwtAuthentication::new(anonymous_routes))
+///     .wrap(RateLimiter::per_client_ip(store, config))
+/// ```
+pub struct RateLimiter {
+    store: Arc<dyn RateLimitStore>,
+    config: Rc<RateLimitConfig>,
+    key_source: BucketKeySource,
+    excluded_routes: Rc<Vec<AnonymousRoute>>,
+}
+
+impl RateLimiter {
+    pub fn new(store: Arc<dyn RateLimitStore>, config: RateLimitConfig) -> Self {
+        Self {
+            store,
+            config: Rc::new(config),
+            key_source: BucketKeySource::Subject,
+            excluded_routes: Rc::new(vec![]),
+        }
+    }
+
+    /// Limiter using `client_ip` bucket for every request
+    pub fn per_client_ip(store: Arc<dyn RateLimitStore>, config: RateLimitConfig) -> Self {
+        Self {
+            store,
+            config: Rc::new(config),
+            key_source: BucketKeySource::ClientIp,
+            excluded_routes: Rc::new(vec![]),
+        }
+    }
+
+    /// Never limit requests to the given routes, e.g. health probes that poll at a fixed rate
+    pub fn with_excluded_routes(mut self, excluded_routes: Vec<AnonymousRoute>) -> Self {
+        self.excluded_routes = Rc::new(excluded_routes);
+        self
+    }
+}
+
+/// Rate limiter middleware
+pub struct RateLimiterMiddleware<S> {
+    /// Service is called from the returned future, after the store has been checked
+    service: Rc<S>,
+    store: Arc<dyn RateLimitStore>,
+    config: Rc<RateLimitConfig>,
+    key_source: BucketKeySource,
+    excluded_routes: Rc<Vec<AnonymousRoute>>,
+}
+
+impl<S> RateLimiterMiddleware<S> {
+    /// Authenticated clients are identified by token subject, anonymous clients by ip.
+    /// Client ip buckets use their own prefix, so anonymous requests aren't charged twice to the same bucket.
+    fn get_bucket_key(&self, req: &ServiceRequest) -> String {
+        if self.key_source == BucketKeySource::Subject {
+            if let Some(claims) = req.extensions().get::<UserClaims>() {
+                return format!("sub:{}", claims.sub);
+            }
+        }
+
+        let connection_info = req.connection_info();
+        let client_ip = if self.config.trust_forwarded_for {
+            connection_info.realip_remote_addr()
+        } else {
+            connection_info.peer_addr()
+        }
+        .unwrap_or("unknown");
+
+        match self.key_source {
+            BucketKeySource::Subject => format!("ip:{client_ip}"),
+            BucketKeySource::ClientIp => format!("client_ip:{client_ip}"),
+        }
+    }
+}
+
+impl<S, B> Service<ServiceRequest> for RateLimiterMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = self.service.clone();
+
+        if self
+            .excluded_routes
+            .iter()
+            .any(|route| route.is_match(&req))
+        {
+            return Box::pin(async move {
+                service
+                    .call(req)
+                    .await
+                    .map(ServiceResponse::map_into_left_body)
+            });
+        }
+
+        let bucket_key = self.get_bucket_key(&req);
+        let store = self.store.clone();
+        let config = self.config.clone();
+
+        Box::pin(async move {
+            let limit = if bucket_key.starts_with("sub:") {
+                &config.authenticated
+            } else if bucket_key.starts_with("client_ip:") {
+                &config.client_ip
+            } else {
+                &config.anonymous
+            };
+
+            // limiter outage must not take the api down, so store errors let the request through
+            let decision = store
+                .try_acquire(&bucket_key, limit)
+                .await
+                .unwrap_or_else(|err| {
+                    error!("failed to check rate limit for {bucket_key}: {err}");
+                    RateLimitDecision::Allowed
+                });
+
+            match decision {
+                RateLimitDecision::Allowed => service
+                    .call(req)
+                    .await
+                    .map(ServiceResponse::map_into_left_body),
+                RateLimitDecision::Limited(retry_after_sec) => {
+                    warn!("{bucket_key} exceeded rate limit");
+
+                    Ok(req.into_response(
+                        ApiError::TooManyRequests(retry_after_sec)
+                            .error_response()
+                            .map_into_right_body(),
+                    ))
+                }
+            }
+        })
+    }
+}
+
+/// Rate limiter middleware factory
+impl<S, B> Transform<S, ServiceRequest> for RateLimiter
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = RateLimiterMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RateLimiterMiddleware {
+            service: Rc::new(service),
+            store: self.store.clone(),
+            config: self.config.clone(),
+            key_source: self.key_source,
+            excluded_routes: self.excluded_routes.clone(),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{
+        dev::Service as _,
+        http::{header::RETRY_AFTER, StatusCode},
+        test, web, App, HttpResponse,
+    };
+
+    use crate::{
+        app_config::RateLimitBucketConfig, rate_limit::rate_limit_store::InMemoryRateLimitStore,
+        test_helpers::create_test_claims,
+    };
+
+    use super::*;
+
+    fn create_test_config() -> RateLimitConfig {
+        RateLimitConfig {
+            anonymous: RateLimitBucketConfig {
+                capacity: 1,
+                refill_per_min: 1,
+            },
+            authenticated: RateLimitBucketConfig {
+                capacity: 2,
+                refill_per_min: 1,
+            },
+            client_ip: RateLimitBucketConfig {
+                capacity: 3,
+                refill_per_min: 1,
+            },
+            ..RateLimitConfig::default()
+        }
+    }
+
+    #[actix_web::test]
+    async fn will_return_429_with_retry_after_over_anonymous_limit() {
+        let uut_app = test::init_service(
+            App::new()
+                .wrap(RateLimiter::new(
+                    Arc::new(InMemoryRateLimitStore::default()),
+                    create_test_config(),
+                ))
+                .route("/test", web::post().to(HttpResponse::Ok)),
+        )
+        .await;
+
+        let first_req = test::TestRequest::post()
+            .uri("/test")
+            .peer_addr("10.0.0.1:1000".parse().unwrap())
+            .to_request();
+        let first_resp = test::call_service(&uut_app, first_req).await;
+
+        let second_req = test::TestRequest::post()
+            .uri("/test")
+            .peer_addr("10.0.0.1:1001".parse().unwrap())
+            .to_request();
+        let second_resp = test::call_service(&uut_app, second_req).await;
+
+        assert_eq!(StatusCode::OK, first_resp.status());
+        assert_eq!(StatusCode::TOO_MANY_REQUESTS, second_resp.status());
+        assert_eq!("60", second_resp.headers().get(RETRY_AFTER).unwrap());
+    }
+
+    #[actix_web::test]
+    async fn will_limit_authenticated_requests_per_subject() {
+        let uut_app = test::init_service(
+            App::new()
+                .wrap(RateLimiter::new(
+                    Arc::new(InMemoryRateLimitStore::default()),
+                    create_test_config(),
+                ))
+                // stands in for the auth middleware
+                .wrap_fn(|req, srv| {
+                    let sub = req.headers().get("x-test-sub").unwrap().to_str().unwrap();
+                    req.extensions_mut().insert(create_test_claims(sub));
+                    srv.call(req)
+                })
+                .route("/test", web::post().to(HttpResponse::Ok)),
+        )
+        .await;
+
+        let mut actual_statuses = vec![];
+        for sub in ["first", "first", "first", "second"] {
+            let req = test::TestRequest::post()
+                .uri("/test")
+                .insert_header(("x-test-sub", sub))
+                .to_request();
+
+            actual_statuses.push(test::call_service(&uut_app, req).await.status());
+        }
+
+        assert_eq!(
+            vec![
+                StatusCode::OK,
+                StatusCode::OK,
+                StatusCode::TOO_MANY_REQUESTS,
+                StatusCode::OK,
+            ],
+            actual_statuses
+        );
+    }
+
+    #[actix_web::test]
+    async fn will_limit_all_requests_per_client_ip() {
+        let uut_app = test::init_service(
+            App::new()
+                // stands in for the auth middleware
+                .wrap_fn(|req, srv| {
+                    let sub = req.headers().get("x-test-sub").unwrap().to_str().unwrap();
+                    req.extensions_mut().insert(create_test_claims(sub));
+                    srv.call(req)
+                })
+                .wrap(RateLimiter::per_client_ip(
+                    Arc::new(InMemoryRateLimitStore::default()),
+                    create_test_config(),
+                ))
+                .route("/test", web::post().to(HttpResponse::Ok)),
+        )
+        .await;
+
+        let mut actual_statuses = vec![];
+        for sub in ["first", "second", "third", "fourth"] {
+            let req = test::TestRequest::post()
+                .uri("/test")
+                .peer_addr("10.0.0.1:1000".parse().unwrap())
+                .insert_header(("x-test-sub", sub))
+                .to_request();
+
+            actual_statuses.push(test::call_service(&uut_app, req).await.status());
+        }
+
+        assert_eq!(
+            vec![
+                StatusCode::OK,
+                StatusCode::OK,
+                StatusCode::OK,
+                StatusCode::TOO_MANY_REQUESTS,
+            ],
+            actual_statuses
+        );
+    }
+
+    #[actix_web::test]
+    async fn will_never_limit_excluded_routes() {
+        let excluded_routes = || vec![AnonymousRoute::prefix("/health")];
+        let store: Arc<dyn RateLimitStore> = Arc::new(InMemoryRateLimitStore::default());
+
+        let uut_app = test::init_service(
+            App::new()
+                .wrap(
+                    RateLimiter::new(store.clone(), create_test_config())
+                        .with_excluded_routes(excluded_routes()),
+                )
+                .wrap(
+                    RateLimiter::per_client_ip(store, create_test_config())
+                        .with_excluded_routes(excluded_routes()),
+                )
+                .route("/health/live", web::get().to(HttpResponse::Ok)),
+        )
+        .await;
+
+        for _ in 0..10 {
+            let req = test::TestRequest::get()
+                .uri("/health/live")
+                .peer_addr("10.0.0.1:1000".parse().unwrap())
+                .to_request();
+
+            assert_eq!(
+                StatusCode::OK,
+                test::call_service(&uut_app, req).await.status()
+            );
+        }
+    }
+}
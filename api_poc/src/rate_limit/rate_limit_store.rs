@@ -0,0 +1,335 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Mutex,
+    time::Duration,
+};
+
+use bson::{doc, DateTime, Document};
+use futures_util::{future::BoxFuture, FutureExt as _};
+use mongodb::{
+    options::{IndexOptions, ReturnDocument},
+    Collection, Database, IndexModel,
+};
+
+use crate::{
+    api_error::{ApiError, ApiResult},
+    app_config::RateLimitBucketConfig,
+};
+
+/// Oldest in-memory buckets are evicted once there are more of them, so spoofed keys can't exhaust memory
+const MAX_IN_MEMORY_BUCKETS: usize = 100_000;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum RateLimitDecision {
+    Allowed,
+    /// Bucket is empty. Carries seconds until the next token is available
+    Limited(u64),
+}
+
+/// Token bucket storage. Taking a token must be atomic, since the same key is used by concurrent requests
+pub trait RateLimitStore: Send + Sync {
+    /// Take a single token from the key bucket. Missing bucket is created full
+    fn try_acquire<'a>(
+        &'a self,
+        key: &'a str,
+        limit: &'a RateLimitBucketConfig,
+    ) -> BoxFuture<'a, ApiResult<RateLimitDecision>>;
+}
+
+/// Tokens refilled per millisecond
+fn get_refill_per_ms(limit: &RateLimitBucketConfig) -> f64 {
+    limit.refill_per_min as f64 / 60_000.0
+}
+
+/// Time for an empty bucket to become full. Full buckets don't need to be stored
+fn get_full_refill_ms(limit: &RateLimitBucketConfig) -> i64 {
+    (limit.capacity as f64 / get_refill_per_ms(limit)).ceil() as i64
+}
+
+fn get_decision(
+    allowed: bool,
+    remaining_tokens: f64,
+    limit: &RateLimitBucketConfig,
+) -> RateLimitDecision {
+    if allowed {
+        return RateLimitDecision::Allowed;
+    }
+
+    let retry_after_ms = (1.0 - remaining_tokens) / get_refill_per_ms(limit);
+
+    // Retry-After is in whole seconds, so round up to avoid an early retry
+    RateLimitDecision::Limited((retry_after_ms / 1000.0).ceil().max(1.0) as u64)
+}
+
+#[derive(Debug)]
+struct TokenBucket {
+    tokens: f64,
+    updated_at_ms: i64,
+}
+
+impl TokenBucket {
+    fn new(limit: &RateLimitBucketConfig, now_ms: i64) -> Self {
+        Self {
+            tokens: limit.capacity as f64,
+            updated_at_ms: now_ms,
+        }
+    }
+
+    fn try_take(&mut self, limit: &RateLimitBucketConfig, now_ms: i64) -> RateLimitDecision {
+        let elapsed_ms = (now_ms - self.updated_at_ms).max(0) as f64;
+
+        self.tokens =
+            (self.tokens + elapsed_ms * get_refill_per_ms(limit)).min(limit.capacity as f64);
+        self.updated_at_ms = now_ms;
+
+        let allowed = self.tokens >= 1.0;
+        if allowed {
+            self.tokens -= 1.0;
+        }
+
+        get_decision(allowed, self.tokens, limit)
+    }
+}
+
+/// Buckets with their keys in creation order, so the oldest bucket can be evicted in constant time
+#[derive(Default)]
+struct InMemoryBuckets {
+    buckets: HashMap<String, TokenBucket>,
+    created_keys: VecDeque<String>,
+}
+
+/// Buckets kept by this api instance. Limits are not shared with other instances
+pub struct InMemoryRateLimitStore {
+    buckets: Mutex<InMemoryBuckets>,
+    max_buckets: usize,
+}
+
+impl Default for InMemoryRateLimitStore {
+    fn default() -> Self {
+        Self::with_max_buckets(MAX_IN_MEMORY_BUCKETS)
+    }
+}
+
+impl InMemoryRateLimitStore {
+    fn with_max_buckets(max_buckets: usize) -> Self {
+        Self {
+            buckets: Mutex::new(InMemoryBuckets::default()),
+            max_buckets,
+        }
+    }
+
+    fn try_acquire_at(
+        &self,
+        key: &str,
+        limit: &RateLimitBucketConfig,
+        now_ms: i64,
+    ) -> ApiResult<RateLimitDecision> {
+        let mut buckets = self
+            .buckets
+            .lock()
+            .map_err(|_| ApiError::Internal("rate limit buckets lock is poisoned!".into()))?;
+        let InMemoryBuckets {
+            buckets,
+            created_keys,
+        } = &mut *buckets;
+
+        if !buckets.contains_key(key) {
+            // evicted bucket starts over full, which only favours the oldest clients
+            while buckets.len() >= self.max_buckets {
+                let Some(oldest_key) = created_keys.pop_front() else {
+                    break;
+                };
+                buckets.remove(&oldest_key);
+            }
+
+            buckets.insert(key.into(), TokenBucket::new(limit, now_ms));
+            created_keys.push_back(key.into());
+        }
+
+        Ok(buckets
+            .get_mut(key)
+            .ok_or_else(|| ApiError::Internal("rate limit bucket was not created!".into()))?
+            .try_take(limit, now_ms))
+    }
+}
+
+impl RateLimitStore for InMemoryRateLimitStore {
+    fn try_acquire<'a>(
+        &'a self,
+        key: &'a str,
+        limit: &'a RateLimitBucketConfig,
+    ) -> BoxFuture<'a, ApiResult<RateLimitDecision>> {
+        let decision = self.try_acquire_at(key, limit, DateTime::now().timestamp_millis());
+
+        async move { decision }.boxed()
+    }
+}
+
+/// Buckets shared by all api instances.
+/// Bucket record is removed by the TTL index once it would be full again.
+pub struct MongoRateLimitStore {
+    mongo_database: Database,
+}
+
+impl MongoRateLimitStore {
+    pub fn new(mongo_database: Database) -> Self {
+        Self { mongo_database }
+    }
+
+    pub fn get_bucket_collection(mongo_database: &Database) -> Collection<Document> {
+        mongo_database.collection::<Document>("rate_limit_buckets")
+    }
+
+    pub async fn create_ttl_index(
+        mongo_database: &Database,
+    ) -> Result<mongodb::results::CreateIndexResult, mongodb::error::Error> {
+        let options = IndexOptions::builder().expire_after(Duration::ZERO).build();
+        let model = IndexModel::builder()
+            .keys(doc! { "expires_at": 1 })
+            .options(options)
+            .build();
+
+        Self::get_bucket_collection(mongo_database)
+            .create_index(model)
+            .await
+    }
+
+    async fn try_acquire_token(
+        &self,
+        key: &str,
+        limit: &RateLimitBucketConfig,
+    ) -> ApiResult<RateLimitDecision> {
+        let now = DateTime::now();
+        let capacity = limit.capacity as f64;
+        let expires_at = DateTime::from_millis(now.timestamp_millis() + get_full_refill_ms(limit));
+
+        // refill and take are done by a single update pipeline, so concurrent requests can't overdraw the bucket.
+        // Second stage expressions see tokens refilled by the first stage.
+        let update_pipeline = vec![
+            doc! { "$set": {
+                "tokens": { "$min": [
+                    capacity,
+                    { "$add": [
+                        { "$ifNull": ["$tokens", capacity] },
+                        { "$multiply": [
+                            { "$subtract": [now, { "$ifNull": ["$updated_at", now] }] },
+                            get_refill_per_ms(limit),
+                        ] },
+                    ] },
+                ] },
+                "updated_at": now,
+            } },
+            doc! { "$set": {
+                "allowed": { "$gte": ["$tokens", 1] },
+                "tokens": { "$cond": [
+                    { "$gte": ["$tokens", 1] },
+                    { "$subtract": ["$tokens", 1] },
+                    "$tokens",
+                ] },
+                "expires_at": expires_at,
+            } },
+        ];
+
+        let bucket = Self::get_bucket_collection(&self.mongo_database)
+            .find_one_and_update(doc! { "_id": key }, update_pipeline)
+            .upsert(true)
+            .return_document(ReturnDocument::After)
+            .await?
+            .ok_or_else(|| ApiError::Internal("rate limit bucket was not upserted!".into()))?;
+
+        let allowed = bucket.get_bool("allowed").unwrap_or(true);
+        let remaining_tokens = bucket.get_f64("tokens").unwrap_or(capacity);
+
+        Ok(get_decision(allowed, remaining_tokens, limit))
+    }
+}
+
+impl RateLimitStore for MongoRateLimitStore {
+    fn try_acquire<'a>(
+        &'a self,
+        key: &'a str,
+        limit: &'a RateLimitBucketConfig,
+    ) -> BoxFuture<'a, ApiResult<RateLimitDecision>> {
+        self.try_acquire_token(key, limit).boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_bucket_count(store: &InMemoryRateLimitStore) -> usize {
+        store.buckets.lock().unwrap().buckets.len()
+    }
+
+    fn create_test_limit() -> RateLimitBucketConfig {
+        RateLimitBucketConfig {
+            capacity: 2,
+            refill_per_min: 60,
+        }
+    }
+
+    #[test]
+    fn will_limit_requests_over_capacity() {
+        let limit = create_test_limit();
+        let uut_store = InMemoryRateLimitStore::default();
+
+        let actual_decisions: Vec<RateLimitDecision> = (0..3)
+            .map(|_| uut_store.try_acquire_at("key", &limit, 0).unwrap())
+            .collect();
+
+        assert_eq!(
+            vec![
+                RateLimitDecision::Allowed,
+                RateLimitDecision::Allowed,
+                RateLimitDecision::Limited(1),
+            ],
+            actual_decisions
+        );
+    }
+
+    #[test]
+    fn will_refill_bucket_over_time() {
+        let limit = create_test_limit();
+        let uut_store = InMemoryRateLimitStore::default();
+
+        uut_store.try_acquire_at("key", &limit, 0).unwrap();
+        uut_store.try_acquire_at("key", &limit, 0).unwrap();
+
+        let actual_decision = uut_store.try_acquire_at("key", &limit, 1000).unwrap();
+
+        assert_eq!(RateLimitDecision::Allowed, actual_decision);
+    }
+
+    #[test]
+    fn will_keep_separate_bucket_per_key() {
+        let limit = create_test_limit();
+        let uut_store = InMemoryRateLimitStore::default();
+
+        uut_store.try_acquire_at("first_key", &limit, 0).unwrap();
+        uut_store.try_acquire_at("first_key", &limit, 0).unwrap();
+
+        let actual_decision = uut_store.try_acquire_at("second_key", &limit, 0).unwrap();
+
+        assert_eq!(RateLimitDecision::Allowed, actual_decision);
+    }
+
+    #[test]
+    fn will_evict_oldest_bucket_over_max_buckets() {
+        let limit = create_test_limit();
+        let uut_store = InMemoryRateLimitStore::with_max_buckets(2);
+
+        uut_store.try_acquire_at("oldest_key", &limit, 0).unwrap();
+        uut_store.try_acquire_at("oldest_key", &limit, 0).unwrap();
+        uut_store.try_acquire_at("second_key", &limit, 0).unwrap();
+        uut_store.try_acquire_at("third_key", &limit, 0).unwrap();
+
+        assert_eq!(2, get_bucket_count(&uut_store));
+
+        // evicted bucket is recreated full
+        let actual_decision = uut_store.try_acquire_at("oldest_key", &limit, 0).unwrap();
+
+        assert_eq!(RateLimitDecision::Allowed, actual_decision);
+        assert_eq!(2, get_bucket_count(&uut_store));
+    }
+}
@@ -0,0 +1,52 @@
+//! Test fixtures shared by unit tests of several modules
+
+use crate::{
+    app_config::AppConfig,
+    auth::{
+        identity_provider::IdentityProviderRegistry,
+        signing_key::{JwtKeySet, JwtSigningKey},
+        token_revocation::RevokedTokenCache,
+        token_service::{JwtTokenService, UserClaims},
+    },
+    game::achievements::AchievementRegistry,
+    AppState,
+};
+
+/// Token service signing HS256 tokens with a fixed test key
+pub fn create_test_token_service() -> JwtTokenService {
+    JwtTokenService::new(
+        JwtKeySet::new(JwtSigningKey::from_secret("test key")),
+        "issuer",
+        "audience",
+        1,
+        1,
+    )
+}
+
+/// App state with default config and no identity providers.
+/// Use struct update syntax to replace single fields, e.g. `AppState { config, ..create_test_app_state() }`
+pub fn create_test_app_state() -> AppState {
+    AppState {
+        token_service: Box::new(create_test_token_service()),
+        config: AppConfig::default(),
+        identity_providers: IdentityProviderRegistry::default(),
+        achievements: AchievementRegistry::default(),
+        revoked_tokens: RevokedTokenCache::default(),
+    }
+}
+
+/// Claims standing in for a validated token, e.g. when the auth middleware is replaced with `wrap_fn`.
+/// Use struct update syntax to set roles
+pub fn create_test_claims(sub: &str) -> UserClaims {
+    UserClaims {
+        sub: sub.into(),
+        aud: "audience".into(),
+        iss: "issuer".into(),
+        exp: 0,
+        nbf: 0,
+        iat: 0,
+        jti: "jti".into(),
+        name: "test player".into(),
+        roles: vec![],
+    }
+}
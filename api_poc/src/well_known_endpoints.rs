@@ -0,0 +1,16 @@
+use std::sync::Arc;
+
+use actix_web::{get, web, HttpResponse, Responder};
+
+use crate::AppState;
+
+/// Public keys for verifying api tokens. See [RFC 7517](https://datatracker.ietf.org/doc/html/rfc7517#section-5)
+#[get("/jwks.json")]
+async fn jwks(data: web::Data<Arc<AppState>>) -> impl Responder {
+    HttpResponse::Ok().json(data.token_service.get_public_jwks())
+}
+
+/// Configure well-known endpoints. Must be registered within `/.well-known` scope and allowed for anonymous access.
+pub fn well_known_config(cfg: &mut web::ServiceConfig) {
+    cfg.service(jwks);
+}
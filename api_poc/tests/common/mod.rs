@@ -1,3 +1,4 @@
+use bson::doc;
 use mongodb::Client;
 
 use std::time::Duration;
@@ -42,3 +43,60 @@ pub async fn get_mongo_client_with_container() -> (ContainerAsync<GenericImage>,
 
     (container, mongo_client)
 }
+
+/// Start single node replica set. Transactions are not supported by standalone mongo.
+/// Replica set is started without auth since auth requires shared key file.
+#[allow(dead_code)]
+pub async fn get_mongo_replica_set_client_with_container() -> (ContainerAsync<GenericImage>, Client)
+{
+    let container = GenericImage::new("mongo", "7.0")
+        .with_wait_for(WaitFor::message_on_stdout("Waiting for connections"))
+        .with_exposed_port(DEFAULT_MONGO_PORT.tcp())
+        .with_cmd(["--replSet", "rs0", "--bind_ip_all"])
+        .with_startup_timeout(Duration::from_secs(30))
+        .start()
+        .await
+        .expect("Mongo container must be started");
+
+    let dynamic_port = &container
+        .ports()
+        .await
+        .expect("Mongo container must have ports")
+        .map_to_host_port_ipv4(DEFAULT_MONGO_PORT.tcp())
+        .expect("mongo container must have 27017 exposed");
+
+    let conn_string =
+        format!("mongodb://localhost:{dynamic_port}/{TEST_DB_NAME}?directConnection=true");
+
+    let mongo_client = Client::with_uri_str(conn_string)
+        .await
+        .expect("Mongo client must be created");
+
+    mongo_client
+        .database("admin")
+        .run_command(doc! {
+            "replSetInitiate": {
+                "_id": "rs0",
+                "members": [{ "_id": 0, "host": format!("localhost:{DEFAULT_MONGO_PORT}") }],
+            }
+        })
+        .await
+        .expect("Replica set must be initiated");
+
+    // node needs a moment to elect itself as primary
+    for _ in 0..50 {
+        let hello = mongo_client
+            .database("admin")
+            .run_command(doc! { "hello": 1 })
+            .await
+            .expect("Mongo must respond to hello");
+
+        if hello.get_bool("isWritablePrimary").unwrap_or_default() {
+            return (container, mongo_client);
+        }
+
+        actix_web::rt::time::sleep(Duration::from_millis(200)).await;
+    }
+
+    panic!("Replica set primary must be elected");
+}
@@ -0,0 +1,61 @@
+#[path = "../src/api_error.rs"]
+mod api_error;
+#[allow(dead_code)]
+#[path = "../src/auth/api_key.rs"]
+mod api_key;
+mod common;
+
+use api_key::ApiKey;
+use bson::DateTime;
+
+pub const TEST_DB_NAME: &str = "test_db";
+
+#[actix_web::test]
+async fn int_will_accept_active_api_key_until_revoked(
+) -> Result<(), Box<dyn std::error::Error + 'static>> {
+    let (_container, mongo_client) = common::get_mongo_client_with_container().await;
+
+    let game_db = mongo_client.database(TEST_DB_NAME);
+
+    let (created_key, plaintext_key) =
+        ApiKey::create(&game_db, "data pipeline", vec!["admin".into()], None).await?;
+
+    assert_ne!(plaintext_key, created_key.key_hash);
+
+    let actual_key = ApiKey::get_active_by_key(&game_db, &plaintext_key)
+        .await?
+        .expect("active key must be found");
+
+    assert_eq!(created_key, actual_key);
+
+    assert!(ApiKey::revoke(&game_db, created_key.id).await?);
+
+    assert!(ApiKey::get_active_by_key(&game_db, &plaintext_key)
+        .await?
+        .is_none());
+
+    let actual_keys = ApiKey::get_all(&game_db).await?;
+
+    assert_eq!(1, actual_keys.len());
+    assert!(actual_keys[0].date_revoked.is_some());
+
+    Ok(())
+}
+
+#[actix_web::test]
+async fn int_will_not_accept_expired_api_key() -> Result<(), Box<dyn std::error::Error + 'static>> {
+    let (_container, mongo_client) = common::get_mongo_client_with_container().await;
+
+    let game_db = mongo_client.database(TEST_DB_NAME);
+
+    let expired_at = DateTime::from_millis(DateTime::now().timestamp_millis() - 60_000);
+
+    let (_, plaintext_key) =
+        ApiKey::create(&game_db, "support script", vec![], Some(expired_at)).await?;
+
+    let actual_key = ApiKey::get_active_by_key(&game_db, &plaintext_key).await?;
+
+    assert!(actual_key.is_none());
+
+    Ok(())
+}
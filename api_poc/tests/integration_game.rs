@@ -0,0 +1,357 @@
+#[path = "../src/api_error.rs"]
+mod api_error;
+mod common;
+#[path = "../src/game/mod.rs"]
+mod game;
+
+use std::collections::HashSet;
+
+use bson::{oid::ObjectId, DateTime};
+use game::{
+    game::{Game, GameStatus},
+    license_plate_enums::{Country, StateOrProvince},
+    license_plates::SpottedPlate,
+    plate_spot::{PlateSpot, SpotLocation},
+    player::Player,
+};
+use mongodb::Database;
+
+pub const TEST_DB_NAME: &str = "test_db";
+
+async fn create_test_player(game_db: &Database) -> Player {
+    Player::create_from_external_identity(
+        game_db,
+        "test player",
+        "test_provider",
+        &ObjectId::new().to_hex(),
+        "test_api_refresh_token",
+        DateTime::now(),
+    )
+    .await
+    .expect("test player must be created")
+}
+
+#[actix_web::test]
+async fn int_will_create_new_game_for_owner() -> Result<(), Box<dyn std::error::Error + 'static>> {
+    // _container must be captured in the variable so the teardown wont happen too soon
+    let (_container, mongo_client) = common::get_mongo_client_with_container().await;
+
+    let game_db = mongo_client.database(TEST_DB_NAME);
+
+    let test_owner = create_test_player(&game_db).await;
+
+    let actual_new_game = Game::create_new_game(&game_db, test_owner.id).await?;
+
+    assert_eq!(test_owner.id, actual_new_game.owner_id);
+    assert_eq!(HashSet::from([test_owner.id]), actual_new_game.participants);
+    assert_eq!(GameStatus::Active, actual_new_game.status);
+    assert!(actual_new_game.date_ended.is_none());
+
+    let actual_game_from_qry = Game::get_game_by_id(&game_db, actual_new_game.id)
+        .await?
+        .expect("test game must be present");
+
+    assert_eq!(actual_new_game, actual_game_from_qry);
+
+    let actual_owner = Player::get_player_by_existing_identity(
+        &game_db,
+        &test_owner.provider_name,
+        &test_owner.provider_identity_id,
+    )
+    .await?
+    .expect("test owner must be present");
+
+    assert!(actual_owner.games_owned.contains(&actual_new_game.id));
+
+    Ok(())
+}
+
+#[actix_web::test]
+async fn int_will_not_create_game_for_missing_owner(
+) -> Result<(), Box<dyn std::error::Error + 'static>> {
+    let (_container, mongo_client) = common::get_mongo_client_with_container().await;
+
+    let game_db = mongo_client.database(TEST_DB_NAME);
+
+    let actual_result = Game::create_new_game(&game_db, ObjectId::new()).await;
+
+    assert!(actual_result.is_err());
+
+    let actual_games_count = Game::get_game_collection(&game_db)
+        .count_documents(bson::doc! {})
+        .await?;
+
+    assert_eq!(0, actual_games_count);
+
+    Ok(())
+}
+
+#[actix_web::test]
+async fn int_will_return_games_for_participant() -> Result<(), Box<dyn std::error::Error + 'static>>
+{
+    let (_container, mongo_client) = common::get_mongo_client_with_container().await;
+
+    let game_db = mongo_client.database(TEST_DB_NAME);
+
+    let test_owner = create_test_player(&game_db).await;
+    let other_player = create_test_player(&game_db).await;
+
+    let first_game = Game::create_new_game(&game_db, test_owner.id).await?;
+    let second_game = Game::create_new_game(&game_db, test_owner.id).await?;
+    Game::create_new_game(&game_db, other_player.id).await?;
+
+    let actual_games = Game::get_games_for_player(&game_db, test_owner.id).await?;
+
+    let actual_game_ids: HashSet<_> = actual_games.iter().map(|game| game.id).collect();
+
+    assert_eq!(
+        HashSet::from([first_game.id, second_game.id]),
+        actual_game_ids
+    );
+
+    Ok(())
+}
+
+#[actix_web::test]
+async fn int_will_add_and_remove_spots() -> Result<(), Box<dyn std::error::Error + 'static>> {
+    let (_container, mongo_client) = common::get_mongo_client_with_container().await;
+
+    let game_db = mongo_client.database(TEST_DB_NAME);
+
+    let test_owner = create_test_player(&game_db).await;
+    let test_game = Game::create_new_game(&game_db, test_owner.id).await?;
+
+    let wa_plate = SpottedPlate {
+        country: Country::US,
+        state_or_province: StateOrProvince::WA,
+    };
+    let bc_plate = SpottedPlate {
+        country: Country::CA,
+        state_or_province: StateOrProvince::BC,
+    };
+
+    let wa_spot = PlateSpot::new(
+        wa_plate,
+        test_owner.id,
+        Some(DateTime::from_millis(1_700_000_000_000)),
+        Some(SpotLocation {
+            latitude: 47.6062,
+            longitude: -122.3321,
+        }),
+    );
+
+    Game::add_spot(&game_db, test_game.id, &wa_spot).await?;
+    // spotting the same plate twice must keep the original spot
+    Game::add_spot(
+        &game_db,
+        test_game.id,
+        &PlateSpot::new(wa_plate, test_owner.id, None, None),
+    )
+    .await?;
+    let actual_game = Game::add_spot(
+        &game_db,
+        test_game.id,
+        &PlateSpot::new(bc_plate, test_owner.id, None, None),
+    )
+    .await?
+    .expect("test game must be updated");
+
+    assert_eq!(vec![wa_plate, bc_plate], actual_game.get_spotted_plates());
+    assert_eq!(wa_spot, actual_game.spots[0]);
+    assert_eq!(test_owner.id, actual_game.spots[1].spotted_by);
+
+    let actual_game = Game::remove_spot(&game_db, test_game.id, test_owner.id, &wa_plate)
+        .await?
+        .expect("test game must be updated");
+
+    assert_eq!(vec![bc_plate], actual_game.get_spotted_plates());
+
+    Ok(())
+}
+
+#[actix_web::test]
+async fn int_will_not_add_spotted_plate_for_non_participant(
+) -> Result<(), Box<dyn std::error::Error + 'static>> {
+    let (_container, mongo_client) = common::get_mongo_client_with_container().await;
+
+    let game_db = mongo_client.database(TEST_DB_NAME);
+
+    let test_owner = create_test_player(&game_db).await;
+    let other_player = create_test_player(&game_db).await;
+    let test_game = Game::create_new_game(&game_db, test_owner.id).await?;
+
+    let wa_plate = SpottedPlate {
+        country: Country::US,
+        state_or_province: StateOrProvince::WA,
+    };
+
+    let actual_game = Game::add_spot(
+        &game_db,
+        test_game.id,
+        &PlateSpot::new(wa_plate, other_player.id, None, None),
+    )
+    .await?;
+
+    assert!(actual_game.is_none());
+
+    Ok(())
+}
+
+#[actix_web::test]
+async fn int_will_end_game_only_once() -> Result<(), Box<dyn std::error::Error + 'static>> {
+    let (_container, mongo_client) = common::get_mongo_client_with_container().await;
+
+    let game_db = mongo_client.database(TEST_DB_NAME);
+
+    let test_owner = create_test_player(&game_db).await;
+    let test_game = Game::create_new_game(&game_db, test_owner.id).await?;
+
+    let actual_ended_game = Game::end_game(&game_db, test_game.id, test_owner.id)
+        .await?
+        .expect("test game must be ended");
+
+    assert_eq!(GameStatus::Ended, actual_ended_game.status);
+    assert!(actual_ended_game.date_ended.is_some());
+
+    let actual_second_end = Game::end_game(&game_db, test_game.id, test_owner.id).await?;
+
+    assert!(actual_second_end.is_none());
+
+    let wa_plate = SpottedPlate {
+        country: Country::US,
+        state_or_province: StateOrProvince::WA,
+    };
+
+    let actual_spot_on_ended_game = Game::add_spot(
+        &game_db,
+        test_game.id,
+        &PlateSpot::new(wa_plate, test_owner.id, None, None),
+    )
+    .await?;
+
+    assert!(actual_spot_on_ended_game.is_none());
+
+    Ok(())
+}
+
+#[actix_web::test]
+async fn int_will_invite_and_accept_player() -> Result<(), Box<dyn std::error::Error + 'static>> {
+    let (_container, mongo_client) = common::get_mongo_replica_set_client_with_container().await;
+
+    let game_db = mongo_client.database(TEST_DB_NAME);
+
+    let test_owner = create_test_player(&game_db).await;
+    let test_invitee = create_test_player(&game_db).await;
+    let test_game = Game::create_new_game(&game_db, test_owner.id).await?;
+
+    let actual_invited_game =
+        Game::invite_player(&game_db, test_game.id, test_owner.id, test_invitee.id)
+            .await?
+            .expect("test game must be updated");
+
+    assert_eq!(
+        HashSet::from([test_invitee.id]),
+        actual_invited_game.invited_players
+    );
+
+    let actual_invitations = Game::get_pending_invitations(&game_db, test_invitee.id).await?;
+
+    assert_eq!(1, actual_invitations.len());
+    assert_eq!(test_game.id, actual_invitations[0].id);
+
+    let actual_joined_game = Game::accept_invitation(&game_db, test_game.id, test_invitee.id)
+        .await?
+        .expect("test game must be updated");
+
+    assert!(actual_joined_game.invited_players.is_empty());
+    assert_eq!(
+        HashSet::from([test_owner.id, test_invitee.id]),
+        actual_joined_game.participants
+    );
+
+    let actual_invitee = Player::get_player_by_id(&game_db, test_invitee.id)
+        .await?
+        .expect("test invitee must be present");
+
+    assert!(actual_invitee.games_invited.is_empty());
+    assert_eq!(HashSet::from([test_game.id]), actual_invitee.games_joined);
+
+    let wa_plate = SpottedPlate {
+        country: Country::US,
+        state_or_province: StateOrProvince::WA,
+    };
+
+    let actual_spotted_game = Game::add_spot(
+        &game_db,
+        test_game.id,
+        &PlateSpot::new(wa_plate, test_invitee.id, None, None),
+    )
+    .await?
+    .expect("test game must be updated");
+
+    assert_eq!(test_invitee.id, actual_spotted_game.spots[0].spotted_by);
+
+    Ok(())
+}
+
+#[actix_web::test]
+async fn int_will_decline_invitation() -> Result<(), Box<dyn std::error::Error + 'static>> {
+    let (_container, mongo_client) = common::get_mongo_replica_set_client_with_container().await;
+
+    let game_db = mongo_client.database(TEST_DB_NAME);
+
+    let test_owner = create_test_player(&game_db).await;
+    let test_invitee = create_test_player(&game_db).await;
+    let test_game = Game::create_new_game(&game_db, test_owner.id).await?;
+
+    Game::invite_player(&game_db, test_game.id, test_owner.id, test_invitee.id).await?;
+
+    let actual_declined_game = Game::decline_invitation(&game_db, test_game.id, test_invitee.id)
+        .await?
+        .expect("test game must be updated");
+
+    assert!(actual_declined_game.invited_players.is_empty());
+    assert_eq!(
+        HashSet::from([test_owner.id]),
+        actual_declined_game.participants
+    );
+
+    let actual_invitee = Player::get_player_by_id(&game_db, test_invitee.id)
+        .await?
+        .expect("test invitee must be present");
+
+    assert!(actual_invitee.games_invited.is_empty());
+    assert!(actual_invitee.games_joined.is_empty());
+
+    let actual_accept = Game::accept_invitation(&game_db, test_game.id, test_invitee.id).await?;
+
+    assert!(actual_accept.is_none());
+
+    Ok(())
+}
+
+#[actix_web::test]
+async fn int_will_not_invite_to_game_not_owned() -> Result<(), Box<dyn std::error::Error + 'static>>
+{
+    let (_container, mongo_client) = common::get_mongo_replica_set_client_with_container().await;
+
+    let game_db = mongo_client.database(TEST_DB_NAME);
+
+    let test_owner = create_test_player(&game_db).await;
+    let other_player = create_test_player(&game_db).await;
+    let test_invitee = create_test_player(&game_db).await;
+    let test_game = Game::create_new_game(&game_db, test_owner.id).await?;
+
+    let actual_invited_game =
+        Game::invite_player(&game_db, test_game.id, other_player.id, test_invitee.id).await?;
+
+    assert!(actual_invited_game.is_none());
+
+    let actual_invitee = Player::get_player_by_id(&game_db, test_invitee.id)
+        .await?
+        .expect("test invitee must be present");
+
+    assert!(actual_invitee.games_invited.is_empty());
+
+    Ok(())
+}
@@ -1,3 +1,5 @@
+#[path = "../src/api_error.rs"]
+mod api_error;
 mod common;
 #[path = "../src/game/mod.rs"]
 mod game;
@@ -5,7 +7,7 @@ mod game;
 use std::collections::HashSet;
 
 use bson::{oid::ObjectId, DateTime};
-use game::player::Player;
+use game::player::{Player, RefreshTokenRotation, GUEST_PROVIDER_NAME, MAX_USED_REFRESH_TOKENS};
 
 pub const TEST_DB_NAME: &str = "test_db";
 
@@ -28,6 +30,11 @@ async fn int_will_return_player_when_exists() -> Result<(), Box<dyn std::error::
 
         games_owned: HashSet::new(),
         games_invited: HashSet::new(),
+        games_joined: HashSet::new(),
+        used_refresh_tokens: HashSet::new(),
+        roles: HashSet::new(),
+        linked_identities: Vec::new(),
+        date_last_active: None,
     };
 
     let writable_collection = Player::get_player_collection(&game_db);
@@ -75,3 +82,559 @@ async fn int_will_create_new_player() -> Result<(), Box<dyn std::error::Error +
 
     Ok(())
 }
+
+#[actix_web::test]
+async fn int_will_report_identity_index_once_created(
+) -> Result<(), Box<dyn std::error::Error + 'static>> {
+    let (_container, mongo_client) = common::get_mongo_client_with_container().await;
+
+    let game_db = mongo_client.database(TEST_DB_NAME);
+
+    // collection must exist before indexes can be listed
+    game_db.create_collection("players").await?;
+
+    assert!(!Player::has_identity_index(&game_db).await?);
+
+    Player::create_identity_index(&game_db)
+        .await
+        .expect("failed to create player index");
+
+    assert!(Player::has_identity_index(&game_db).await?);
+
+    Ok(())
+}
+
+#[actix_web::test]
+async fn int_will_rotate_refresh_token_and_revoke_family_on_reuse(
+) -> Result<(), Box<dyn std::error::Error + 'static>> {
+    let (_container, mongo_client) = common::get_mongo_client_with_container().await;
+
+    let game_db = mongo_client.database(TEST_DB_NAME);
+
+    let test_player = Player::create_from_external_identity(
+        &game_db,
+        "test player",
+        "test_provider",
+        "test_provider_identity_id",
+        "",
+        DateTime::now(),
+    )
+    .await?;
+
+    let token_exp = DateTime::from_millis(DateTime::now().timestamp_millis() + 60_000);
+
+    Player::start_refresh_token_family(&game_db, test_player.id, "first_hash", token_exp).await?;
+
+    let actual_rotation = Player::rotate_refresh_token(
+        &game_db,
+        test_player.id,
+        "first_hash",
+        "second_hash",
+        token_exp,
+    )
+    .await?;
+
+    assert_eq!(RefreshTokenRotation::Rotated, actual_rotation);
+
+    // first token was already used, so the whole family must be revoked
+    let actual_rotation = Player::rotate_refresh_token(
+        &game_db,
+        test_player.id,
+        "first_hash",
+        "third_hash",
+        token_exp,
+    )
+    .await?;
+
+    assert_eq!(RefreshTokenRotation::Reused, actual_rotation);
+
+    let actual_rotation = Player::rotate_refresh_token(
+        &game_db,
+        test_player.id,
+        "second_hash",
+        "third_hash",
+        token_exp,
+    )
+    .await?;
+
+    assert_eq!(RefreshTokenRotation::Invalid, actual_rotation);
+
+    Ok(())
+}
+
+#[actix_web::test]
+async fn int_will_detect_refresh_token_reuse_after_revocation(
+) -> Result<(), Box<dyn std::error::Error + 'static>> {
+    let (_container, mongo_client) = common::get_mongo_client_with_container().await;
+
+    let game_db = mongo_client.database(TEST_DB_NAME);
+
+    let test_player = Player::create_from_external_identity(
+        &game_db,
+        "test player",
+        "test_provider",
+        "test_provider_identity_id",
+        "",
+        DateTime::now(),
+    )
+    .await?;
+
+    let token_exp = DateTime::from_millis(DateTime::now().timestamp_millis() + 60_000);
+
+    Player::start_refresh_token_family(&game_db, test_player.id, "first_hash", token_exp).await?;
+    Player::rotate_refresh_token(
+        &game_db,
+        test_player.id,
+        "first_hash",
+        "second_hash",
+        token_exp,
+    )
+    .await?;
+
+    Player::revoke_refresh_token(&game_db, test_player.id).await?;
+
+    let actual_rotation = Player::rotate_refresh_token(
+        &game_db,
+        test_player.id,
+        "second_hash",
+        "third_hash",
+        token_exp,
+    )
+    .await?;
+
+    assert_eq!(RefreshTokenRotation::Invalid, actual_rotation);
+
+    // token stolen before the logout
+    let actual_rotation = Player::rotate_refresh_token(
+        &game_db,
+        test_player.id,
+        "first_hash",
+        "third_hash",
+        token_exp,
+    )
+    .await?;
+
+    assert_eq!(RefreshTokenRotation::Reused, actual_rotation);
+
+    Ok(())
+}
+
+#[actix_web::test]
+async fn int_will_keep_bounded_window_of_used_refresh_tokens(
+) -> Result<(), Box<dyn std::error::Error + 'static>> {
+    let (_container, mongo_client) = common::get_mongo_client_with_container().await;
+
+    let game_db = mongo_client.database(TEST_DB_NAME);
+
+    let test_player = Player::create_from_external_identity(
+        &game_db,
+        "test player",
+        "test_provider",
+        "test_provider_identity_id",
+        "",
+        DateTime::now(),
+    )
+    .await?;
+
+    let token_exp = DateTime::from_millis(DateTime::now().timestamp_millis() + 60_000);
+
+    Player::start_refresh_token_family(&game_db, test_player.id, "hash_0", token_exp).await?;
+
+    for rotation in 0..=MAX_USED_REFRESH_TOKENS {
+        Player::rotate_refresh_token(
+            &game_db,
+            test_player.id,
+            &format!("hash_{rotation}"),
+            &format!("hash_{}", rotation + 1),
+            token_exp,
+        )
+        .await?;
+    }
+
+    let actual_player = Player::get_player_by_id(&game_db, test_player.id)
+        .await?
+        .unwrap();
+
+    assert_eq!(
+        MAX_USED_REFRESH_TOKENS as usize,
+        actual_player.used_refresh_tokens.len()
+    );
+    assert!(!actual_player.used_refresh_tokens.contains("hash_0"));
+    assert!(actual_player
+        .used_refresh_tokens
+        .contains(&format!("hash_{MAX_USED_REFRESH_TOKENS}")));
+
+    Ok(())
+}
+
+#[actix_web::test]
+async fn int_will_not_rotate_expired_refresh_token(
+) -> Result<(), Box<dyn std::error::Error + 'static>> {
+    let (_container, mongo_client) = common::get_mongo_client_with_container().await;
+
+    let game_db = mongo_client.database(TEST_DB_NAME);
+
+    let test_player = Player::create_from_external_identity(
+        &game_db,
+        "test player",
+        "test_provider",
+        "test_provider_identity_id",
+        "",
+        DateTime::now(),
+    )
+    .await?;
+
+    let expired_token_exp = DateTime::from_millis(DateTime::now().timestamp_millis() - 60_000);
+
+    Player::start_refresh_token_family(&game_db, test_player.id, "first_hash", expired_token_exp)
+        .await?;
+
+    let actual_rotation = Player::rotate_refresh_token(
+        &game_db,
+        test_player.id,
+        "first_hash",
+        "second_hash",
+        DateTime::now(),
+    )
+    .await?;
+
+    assert_eq!(RefreshTokenRotation::Invalid, actual_rotation);
+
+    Ok(())
+}
+
+#[actix_web::test]
+async fn int_will_set_player_roles() -> Result<(), Box<dyn std::error::Error + 'static>> {
+    let (_container, mongo_client) = common::get_mongo_client_with_container().await;
+
+    let game_db = mongo_client.database(TEST_DB_NAME);
+
+    let test_player = Player::create_from_external_identity(
+        &game_db,
+        "test player",
+        "test_provider",
+        "test_provider_identity_id",
+        "",
+        DateTime::now(),
+    )
+    .await?;
+
+    let expected_roles = HashSet::from(["admin".to_string()]);
+
+    Player::set_roles(&game_db, test_player.id, &expected_roles).await?;
+
+    let actual_player = Player::get_player_by_id(&game_db, test_player.id)
+        .await?
+        .expect("test player must be present");
+
+    assert_eq!(expected_roles, actual_player.roles);
+
+    Ok(())
+}
+
+#[actix_web::test]
+async fn int_will_find_player_by_linked_identity(
+) -> Result<(), Box<dyn std::error::Error + 'static>> {
+    let (_container, mongo_client) = common::get_mongo_client_with_container().await;
+
+    let game_db = mongo_client.database(TEST_DB_NAME);
+
+    Player::create_linked_identity_index(&game_db)
+        .await
+        .expect("failed to create linked identity index");
+
+    let test_player = Player::create_from_external_identity(
+        &game_db,
+        "test player",
+        "google",
+        "google_identity_id",
+        "",
+        DateTime::now(),
+    )
+    .await?;
+
+    Player::link_identity(&game_db, test_player.id, "apple", "apple_identity_id").await?;
+
+    let actual_player =
+        Player::get_player_by_existing_identity(&game_db, "apple", "apple_identity_id")
+            .await?
+            .expect("linked identity must resolve to the player");
+
+    assert_eq!(test_player.id, actual_player.id);
+
+    Ok(())
+}
+
+#[actix_web::test]
+async fn int_will_not_link_identity_of_another_player(
+) -> Result<(), Box<dyn std::error::Error + 'static>> {
+    let (_container, mongo_client) = common::get_mongo_client_with_container().await;
+
+    let game_db = mongo_client.database(TEST_DB_NAME);
+
+    let test_player = Player::create_from_external_identity(
+        &game_db,
+        "test player",
+        "google",
+        "google_identity_id",
+        "",
+        DateTime::now(),
+    )
+    .await?;
+
+    Player::create_from_external_identity(
+        &game_db,
+        "other player",
+        "apple",
+        "apple_identity_id",
+        "",
+        DateTime::now(),
+    )
+    .await?;
+
+    let actual_err = Player::link_identity(&game_db, test_player.id, "apple", "apple_identity_id")
+        .await
+        .unwrap_err();
+
+    assert!(matches!(actual_err, api_error::ApiError::Conflict(_)));
+
+    Ok(())
+}
+
+#[actix_web::test]
+async fn int_will_not_link_guest_identity() -> Result<(), Box<dyn std::error::Error + 'static>> {
+    let (_container, mongo_client) = common::get_mongo_client_with_container().await;
+
+    let game_db = mongo_client.database(TEST_DB_NAME);
+
+    let test_player = Player::create_from_external_identity(
+        &game_db,
+        "test player",
+        "google",
+        "google_identity_id",
+        "",
+        DateTime::now(),
+    )
+    .await?;
+
+    let actual_err = Player::link_identity(
+        &game_db,
+        test_player.id,
+        GUEST_PROVIDER_NAME,
+        "device_secret_hash",
+    )
+    .await
+    .unwrap_err();
+
+    assert!(matches!(actual_err, api_error::ApiError::Validation(_)));
+
+    Ok(())
+}
+
+#[actix_web::test]
+async fn int_will_merge_players() -> Result<(), Box<dyn std::error::Error + 'static>> {
+    let (_container, mongo_client) = common::get_mongo_replica_set_client_with_container().await;
+
+    let game_db = mongo_client.database(TEST_DB_NAME);
+
+    // indexes are created at startup, outside of any transaction
+    Player::create_identity_index(&game_db).await?;
+    Player::create_linked_identity_index(&game_db).await?;
+
+    let target_player = Player::create_from_external_identity(
+        &game_db,
+        "target player",
+        "google",
+        "google_identity_id",
+        "",
+        DateTime::now(),
+    )
+    .await?;
+
+    let source_player = Player::create_from_external_identity(
+        &game_db,
+        "source player",
+        "apple",
+        "apple_identity_id",
+        "",
+        DateTime::now(),
+    )
+    .await?;
+
+    let source_game = game::game::Game::create_new_game(&game_db, source_player.id).await?;
+
+    let actual_player = Player::merge_players(&game_db, target_player.id, source_player.id).await?;
+
+    assert!(actual_player.games_owned.contains(&source_game.id));
+    assert!(Player::get_player_by_id(&game_db, source_player.id)
+        .await?
+        .is_none());
+
+    let actual_identity_owner =
+        Player::get_player_by_existing_identity(&game_db, "apple", "apple_identity_id")
+            .await?
+            .expect("source identity must resolve to the merged player");
+
+    assert_eq!(target_player.id, actual_identity_owner.id);
+
+    let actual_game = game::game::Game::get_game_by_id(&game_db, source_game.id)
+        .await?
+        .expect("game must be present");
+
+    assert_eq!(target_player.id, actual_game.owner_id);
+    assert_eq!(HashSet::from([target_player.id]), actual_game.participants);
+
+    Ok(())
+}
+
+#[actix_web::test]
+async fn int_will_upgrade_guest_player() -> Result<(), Box<dyn std::error::Error + 'static>> {
+    let (_container, mongo_client) = common::get_mongo_client_with_container().await;
+
+    let game_db = mongo_client.database(TEST_DB_NAME);
+
+    let guest_player = Player::create_from_external_identity(
+        &game_db,
+        "guest",
+        GUEST_PROVIDER_NAME,
+        "device_secret_hash",
+        "",
+        DateTime::now(),
+    )
+    .await?;
+
+    let guest_game = game::game::Game::create_new_game(&game_db, guest_player.id).await?;
+
+    let actual_player = Player::upgrade_guest(
+        &game_db,
+        guest_player.id,
+        "test player",
+        "google",
+        "google_identity_id",
+    )
+    .await?
+    .expect("guest must be upgraded");
+
+    assert_eq!(guest_player.id, actual_player.id);
+    assert!(!actual_player.is_guest());
+    assert!(actual_player.games_owned.contains(&guest_game.id));
+
+    // upgraded player is no longer a guest
+    let actual_second_upgrade = Player::upgrade_guest(
+        &game_db,
+        guest_player.id,
+        "test player",
+        "apple",
+        "apple_identity_id",
+    )
+    .await?;
+
+    assert!(actual_second_upgrade.is_none());
+
+    Ok(())
+}
+
+#[actix_web::test]
+async fn int_will_purge_inactive_guests() -> Result<(), Box<dyn std::error::Error + 'static>> {
+    let (_container, mongo_client) = common::get_mongo_replica_set_client_with_container().await;
+
+    let game_db = mongo_client.database(TEST_DB_NAME);
+
+    let guest_player = Player::create_from_external_identity(
+        &game_db,
+        "guest",
+        GUEST_PROVIDER_NAME,
+        "device_secret_hash",
+        "",
+        DateTime::now(),
+    )
+    .await?;
+
+    let full_player = Player::create_from_external_identity(
+        &game_db,
+        "test player",
+        "google",
+        "google_identity_id",
+        "",
+        DateTime::now(),
+    )
+    .await?;
+
+    let guest_game = game::game::Game::create_new_game(&game_db, guest_player.id).await?;
+
+    let guest_invited_game = game::game::Game::create_new_game(&game_db, guest_player.id).await?;
+    game::game::Game::invite_player(
+        &game_db,
+        guest_invited_game.id,
+        guest_player.id,
+        full_player.id,
+    )
+    .await?;
+
+    let guest_shared_game = game::game::Game::create_new_game(&game_db, guest_player.id).await?;
+    game::game::Game::invite_player(
+        &game_db,
+        guest_shared_game.id,
+        guest_player.id,
+        full_player.id,
+    )
+    .await?;
+    game::game::Game::accept_invitation(&game_db, guest_shared_game.id, full_player.id).await?;
+
+    let full_player_game = game::game::Game::create_new_game(&game_db, full_player.id).await?;
+    game::game::Game::invite_player(
+        &game_db,
+        full_player_game.id,
+        full_player.id,
+        guest_player.id,
+    )
+    .await?;
+
+    // both players are inactive since the cutoff is in the future
+    let inactive_since = DateTime::from_millis(DateTime::now().timestamp_millis() + 60_000);
+
+    let actual_purged_ids = Player::purge_inactive_guests(&game_db, inactive_since).await?;
+
+    assert_eq!(vec![guest_player.id], actual_purged_ids);
+    assert!(Player::get_player_by_id(&game_db, guest_player.id)
+        .await?
+        .is_none());
+    assert!(game::game::Game::get_game_by_id(&game_db, guest_game.id)
+        .await?
+        .is_none());
+    assert!(
+        game::game::Game::get_game_by_id(&game_db, guest_invited_game.id)
+            .await?
+            .is_none()
+    );
+
+    // game joined by another player is handed over instead of deleted
+    let actual_shared_game = game::game::Game::get_game_by_id(&game_db, guest_shared_game.id)
+        .await?
+        .expect("shared game must be kept");
+
+    assert_eq!(full_player.id, actual_shared_game.owner_id);
+    assert_eq!(
+        HashSet::from([full_player.id]),
+        actual_shared_game.participants
+    );
+
+    let actual_full_player = Player::get_player_by_id(&game_db, full_player.id)
+        .await?
+        .expect("full player must be kept");
+
+    assert_eq!(
+        HashSet::from([full_player_game.id, guest_shared_game.id]),
+        actual_full_player.games_owned
+    );
+    assert!(actual_full_player.games_joined.is_empty());
+    assert!(actual_full_player.games_invited.is_empty());
+
+    let actual_full_player_game = game::game::Game::get_game_by_id(&game_db, full_player_game.id)
+        .await?
+        .expect("full player game must be present");
+
+    assert!(actual_full_player_game.invited_players.is_empty());
+
+    Ok(())
+}
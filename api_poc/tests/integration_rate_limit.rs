@@ -0,0 +1,54 @@
+#[path = "../src/api_error.rs"]
+mod api_error;
+#[allow(dead_code)]
+#[path = "../src/app_config.rs"]
+mod app_config;
+mod common;
+#[allow(dead_code)]
+#[path = "../src/rate_limit/rate_limit_store.rs"]
+mod rate_limit_store;
+
+use app_config::RateLimitBucketConfig;
+use rate_limit_store::{MongoRateLimitStore, RateLimitDecision, RateLimitStore};
+
+pub const TEST_DB_NAME: &str = "test_db";
+
+#[actix_web::test]
+async fn int_will_share_bucket_across_stores() -> Result<(), Box<dyn std::error::Error + 'static>> {
+    let (_container, mongo_client) = common::get_mongo_client_with_container().await;
+
+    let game_db = mongo_client.database(TEST_DB_NAME);
+
+    MongoRateLimitStore::create_ttl_index(&game_db)
+        .await
+        .expect("failed to create rate limit buckets index");
+
+    let limit = RateLimitBucketConfig {
+        capacity: 2,
+        refill_per_min: 1,
+    };
+
+    // stores stand in for two api instances
+    let first_store = MongoRateLimitStore::new(game_db.clone());
+    let second_store = MongoRateLimitStore::new(game_db.clone());
+
+    let mut actual_decisions = vec![];
+    for store in [&first_store, &second_store, &first_store] {
+        actual_decisions.push(store.try_acquire("ip:10.0.0.1", &limit).await?);
+    }
+
+    assert_eq!(
+        vec![
+            RateLimitDecision::Allowed,
+            RateLimitDecision::Allowed,
+            RateLimitDecision::Limited(60),
+        ],
+        actual_decisions
+    );
+
+    let actual_other_key_decision = second_store.try_acquire("ip:10.0.0.2", &limit).await?;
+
+    assert_eq!(RateLimitDecision::Allowed, actual_other_key_decision);
+
+    Ok(())
+}
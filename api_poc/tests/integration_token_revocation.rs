@@ -0,0 +1,60 @@
+#[path = "../src/api_error.rs"]
+mod api_error;
+mod common;
+#[path = "../src/auth/token_revocation.rs"]
+mod token_revocation;
+
+use bson::DateTime;
+use token_revocation::{RevokedToken, RevokedTokenCache};
+
+pub const TEST_DB_NAME: &str = "test_db";
+
+#[actix_web::test]
+async fn int_will_sync_revoked_tokens_to_cache() -> Result<(), Box<dyn std::error::Error + 'static>>
+{
+    let (_container, mongo_client) = common::get_mongo_client_with_container().await;
+
+    let game_db = mongo_client.database(TEST_DB_NAME);
+
+    RevokedToken::create_ttl_index(&game_db)
+        .await
+        .expect("failed to create revoked tokens index");
+
+    let active_exp = DateTime::from_millis(DateTime::now().timestamp_millis() + 60_000);
+    let expired_exp = DateTime::from_millis(DateTime::now().timestamp_millis() - 60_000);
+
+    RevokedToken::revoke(&game_db, "active_jti", active_exp).await?;
+    RevokedToken::revoke(&game_db, "expired_jti", expired_exp).await?;
+
+    let uut_cache = RevokedTokenCache::default();
+    uut_cache.sync(&game_db).await?;
+
+    assert!(uut_cache.is_revoked("active_jti"));
+    assert!(!uut_cache.is_revoked("expired_jti"));
+
+    Ok(())
+}
+
+#[actix_web::test]
+async fn int_will_ignore_repeated_revocation() -> Result<(), Box<dyn std::error::Error + 'static>> {
+    let (_container, mongo_client) = common::get_mongo_client_with_container().await;
+
+    let game_db = mongo_client.database(TEST_DB_NAME);
+
+    let token_exp = DateTime::from_millis(DateTime::now().timestamp_millis() + 60_000);
+
+    RevokedToken::revoke(&game_db, "revoked_jti", token_exp).await?;
+    RevokedToken::revoke(&game_db, "revoked_jti", token_exp).await?;
+
+    let actual_revoked_tokens = RevokedToken::get_active(&game_db).await?;
+
+    assert_eq!(
+        vec![RevokedToken {
+            jti: "revoked_jti".into(),
+            expires_at: token_exp,
+        }],
+        actual_revoked_tokens
+    );
+
+    Ok(())
+}